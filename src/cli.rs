@@ -1,18 +1,65 @@
 use std::any::{Any, TypeId};
 use std::error::Error;
+use std::path::PathBuf;
+use std::process::ExitCode;
 
 use dialoguer::{Confirm, Select};
 use structopt::StructOpt;
 
 use crate::{create_image, utils};
-use crate::requirements::{CheckResult, checks, Requirement, run_requirements};
+use crate::requirements::{run_requirements, run_undo, SystemRoot};
+use crate::requirements::fix::UndoTarget;
+use crate::requirements::os_matrix::OsMatrix;
+use crate::requirements::report::OutputFormat;
 
 #[derive(StructOpt)]
 #[structopt(name = "vpc-migration-tools", about = "A collection of useful tools.")]
 enum Cli {
     #[structopt(about = "Checks if the system is prepared to be used as an image for IBM Cloud Virtual Servers for VPC.\
      There are some requirements that might be fixed automatically.")]
-    CheckRequirements,
+    CheckRequirements {
+        #[structopt(long = "root", parse(from_os_str), help = "Check a mounted or extracted system tree instead of the running host.")]
+        root: Option<PathBuf>,
+
+        #[structopt(long = "format", default_value = "text", possible_values = OutputFormat::VARIANTS, help = "The format of the report.")]
+        format: OutputFormat,
+
+        #[structopt(long = "output", parse(from_os_str), help = "Write the report to this file instead of the standard output.")]
+        output: Option<PathBuf>,
+
+        #[structopt(long = "yes", short = "y", help = "Apply every available fix without asking.")]
+        yes: bool,
+
+        #[structopt(long = "fix", use_delimiter = true, conflicts_with_all = &["yes", "no_fix"], help = "Apply only the fixes of these requirements, without asking, e.g. --fix kernel-args.")]
+        fix: Vec<String>,
+
+        #[structopt(long = "no-fix", conflicts_with = "yes", help = "Only report, never apply a fix.")]
+        no_fix: bool,
+
+        #[structopt(long = "dry-run", conflicts_with = "no_fix", help = "Show the files the fixes would modify and the commands they would run, without applying them.")]
+        dry_run: bool,
+
+        #[structopt(long = "resume-unit", conflicts_with_all = &["no_fix", "dry_run"], help = "Install a one-shot systemd unit that confirms the fixes pending a reboot after the next boot.")]
+        resume_unit: bool,
+
+        #[structopt(long = "os-matrix", parse(from_os_str), help = "Use this file of supported operating systems instead of the embedded one.")]
+        os_matrix: Option<PathBuf>,
+    },
+
+    #[structopt(about = "Reverts a fix applied by check-requirements, restoring the files it changed.")]
+    Undo {
+        #[structopt(long = "root", parse(from_os_str), help = "Undo a fix applied to a mounted or extracted system tree instead of the running host.")]
+        root: Option<PathBuf>,
+
+        #[structopt(long = "last", conflicts_with = "id", help = "Undo the most recent fix that was not undone yet. This is the default.")]
+        last: bool,
+
+        #[structopt(long = "id", help = "Undo the fix recorded in the journal with this id.")]
+        id: Option<String>,
+
+        #[structopt(long = "list", conflicts_with_all = &["last", "id"], help = "List the fixes recorded in the journal.")]
+        list: bool,
+    },
 
     #[structopt(about = "Create a disk image.")]
     CreateImage {
@@ -27,12 +74,38 @@ enum Cli {
     },
 }
 
-pub fn run() -> Result<(), Box<dyn Error>> {
+pub fn run() -> Result<ExitCode, Box<dyn Error>> {
     let cli = Cli::from_args();
 
     match cli {
-        Cli::CheckRequirements => {
-            run_requirements::run_requirements()
+        Cli::CheckRequirements { root, format, output, yes, fix, no_fix, dry_run, resume_unit, os_matrix } => {
+            let root = system_root(root)?;
+            let os_matrix = match os_matrix {
+                Some(path) => OsMatrix::load(&path)?,
+                None => OsMatrix::embedded()?,
+            };
+
+            let fix_mode = if no_fix {
+                run_requirements::FixMode::Never
+            } else if yes {
+                run_requirements::FixMode::All
+            } else if !fix.is_empty() {
+                run_requirements::FixMode::Only(fix)
+            } else {
+                run_requirements::FixMode::Prompt
+            };
+
+            run_requirements::run_requirements(run_requirements::Options { root, format, output, fix_mode, dry_run, resume_unit, os_matrix })
+        }
+        Cli::Undo { root, last, id, list } => {
+            let root = system_root(root)?;
+            let target = match id {
+                // --last and --id conflict, --last is only there to be explicit
+                Some(id) if !last => UndoTarget::Id(id),
+                _ => UndoTarget::Last,
+            };
+
+            run_undo::run_undo(run_undo::Options { root, list, target })
         }
         Cli::CreateImage { image_name, dir, skip_free_space } => {
             let device_list = create_image::partitions::list_available_devices()?;
@@ -43,11 +116,23 @@ pub fn run() -> Result<(), Box<dyn Error>> {
                 dir,
                 // device comes from option without /
                 device: device.value,
-            })
+            })?;
+            Ok(ExitCode::SUCCESS)
         }
     }
 }
 
+fn system_root(root: Option<PathBuf>) -> Result<SystemRoot, Box<dyn Error>> {
+    match root {
+        Some(root) => {
+            if !root.is_dir() {
+                return Err(format!("Invalid root directory: {}", root.display()).into());
+            }
+            Ok(SystemRoot::new(root))
+        }
+        None => Ok(SystemRoot::host()),
+    }
+}
 
 fn ask_user_from_list(list: Vec<utils::Option>, message: &str) -> Result<utils::Option, Box<dyn Error>> {
     let mut list = list;
@@ -5,7 +5,7 @@ use std::error::Error;
 use std::fs;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Read};
-use std::process::Command;
+use std::process::{Command, ExitCode};
 
 use sysinfo::{DiskExt, System, SystemExt};
 
@@ -19,7 +19,7 @@ mod create_image;
 #[cfg(test)]
 mod tests;
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> Result<ExitCode, Box<dyn Error>> {
     set_default_log_level();
     run()
 }
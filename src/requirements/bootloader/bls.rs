@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::requirements::SystemRoot;
+use crate::utils::{self, FileError};
+
+pub const BLS_ENTRIES_DIR: &str = "/boot/loader/entries";
+pub const GRUBENV_PATHS: [&str; 2] = ["/boot/grub2/grubenv", "/boot/grub/grubenv"];
+
+/// A Boot Loader Specification entry, as used by RHEL 8+, Rocky and Fedora.
+#[derive(Debug, Clone)]
+pub struct BlsEntry {
+    /// The file name without `.conf`, which is what `saved_entry` refers to.
+    pub id: String,
+    /// The absolute path of the entry in the target system.
+    pub path: String,
+    pub version: String,
+    /// The kernel arguments, possibly referring to GRUB environment variables such as `$kernelopts`.
+    pub options: String,
+}
+
+pub fn has_entries(root: &SystemRoot) -> bool {
+    entries(root).map(|entries| !entries.is_empty()).unwrap_or(false)
+}
+
+/// Lists the entries, oldest kernel first.
+pub fn entries(root: &SystemRoot) -> Result<Vec<BlsEntry>, FileError> {
+    let dir = root.path(BLS_ENTRIES_DIR);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut entries = vec![];
+    for dir_entry in fs::read_dir(&dir).map_err(|e| FileError::new("read directory", BLS_ENTRIES_DIR, e))? {
+        let file_name = dir_entry.map_err(|e| FileError::new("read directory", BLS_ENTRIES_DIR, e))?.file_name();
+        let file_name = file_name.to_string_lossy();
+        let id = match file_name.strip_suffix(".conf") {
+            Some(id) => id.to_string(),
+            None => continue,
+        };
+
+        let path = format!("{}/{}", BLS_ENTRIES_DIR, file_name);
+        let content = root.read_to_string(&path)?;
+        let field = |key: &str| {
+            content
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(key).filter(|rest| rest.starts_with([' ', '\t'])))
+                .map(|value| value.trim().to_string())
+        };
+
+        entries.push(BlsEntry {
+            version: field("version").unwrap_or_else(|| id.clone()),
+            options: field("options").unwrap_or_default(),
+            id,
+            path,
+        });
+    }
+
+    entries.sort_by(|a, b| utils::compare_versions(&a.version, &b.version));
+    Ok(entries)
+}
+
+/// Reads the GRUB environment block, a list of `key=value` lines padded with `#`.
+pub fn grubenv(root: &SystemRoot) -> HashMap<String, String> {
+    GRUBENV_PATHS
+        .iter()
+        .find_map(|path| root.read_to_string(path).ok())
+        .map(|content| {
+            content
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .filter_map(|line| line.split_once('='))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The kernel arguments of the entry GRUB boots by default: the `saved_entry`
+/// of the GRUB environment, or else the newest kernel.
+pub fn default_options(root: &SystemRoot) -> Result<Option<String>, FileError> {
+    let entries = entries(root)?;
+    let env = grubenv(root);
+
+    let entry = env
+        .get("saved_entry")
+        .and_then(|saved| entries.iter().find(|entry| &entry.id == saved))
+        .or_else(|| entries.last());
+
+    Ok(entry.map(|entry| expand(&entry.options, &env)))
+}
+
+/// Expands the `$name` references to GRUB environment variables, e.g. `$kernelopts` on RHEL 8.
+fn expand(options: &str, env: &HashMap<String, String>) -> String {
+    options
+        .split_whitespace()
+        .map(|arg| match arg.strip_prefix('$') {
+            Some(name) => env.get(name).cloned().unwrap_or_default(),
+            None => arg.to_string(),
+        })
+        .filter(|arg| !arg.is_empty())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[test]
+fn test_default_options_expand_grubenv() {
+    let root = crate::tests::fixture_root("bls-entries", &[
+        ("/boot/loader/entries/abc-4.18.0-477.el8.x86_64.conf", "title Rocky Linux\nversion 4.18.0-477.el8.x86_64\noptions $kernelopts $tuned_params\n"),
+        ("/boot/loader/entries/abc-4.18.0-80.el8.x86_64.conf", "title Rocky Linux\nversion 4.18.0-80.el8.x86_64\noptions root=/dev/sda1 ro\n"),
+        ("/boot/grub2/grubenv", "# GRUB Environment Block\nkernelopts=root=/dev/sda1 ro console=ttyS0\n#########\n"),
+    ]);
+
+    let entries = entries(&root).unwrap();
+
+    assert_eq!(entries.last().unwrap().id, "abc-4.18.0-477.el8.x86_64");
+    assert_eq!(default_options(&root).unwrap().unwrap(), "root=/dev/sda1 ro console=ttyS0");
+}
@@ -0,0 +1,31 @@
+/// Splits a kernel command line into arguments like the kernel does: on whitespace,
+/// except between double quotes, which are removed, e.g. `dyndbg="file x.c +p"`.
+pub fn parse(cmdline: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in cmdline.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+
+    args
+}
+
+#[test]
+fn test_parse_respects_quotes() {
+    let args = parse("BOOT_IMAGE=/vmlinuz root=UUID=1234 dyndbg=\"file x.c +p\" console=ttyS01\n");
+
+    assert_eq!(args, vec!["BOOT_IMAGE=/vmlinuz", "root=UUID=1234", "dyndbg=file x.c +p", "console=ttyS01"]);
+}
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+use crate::requirements::SystemRoot;
+use crate::utils::shell_assignments::{ShellFile, ShellFileError};
+use crate::utils::FileError;
+
+pub const GRUB_DEFAULTS: &str = "/etc/default/grub";
+pub const GRUB_DEFAULTS_DIR: &str = "/etc/default/grub.d";
+
+/// A GRUB defaults file, such as `/etc/default/grub`, a shell script sourced by `grub-mkconfig`.
+#[derive(Debug, Clone)]
+pub struct GrubDefaults {
+    file: ShellFile,
+}
+
+impl GrubDefaults {
+    /// Parses a file, expanding variables with the ones in `env`, which is then updated
+    /// with the assignments of the file.
+    pub fn parse(path: &str, content: &str, env: &mut HashMap<String, String>) -> Result<Self, ShellFileError> {
+        Ok(GrubDefaults { file: ShellFile::parse(path, content, env)? })
+    }
+
+    pub fn path(&self) -> &str {
+        self.file.path()
+    }
+
+    pub fn content(&self) -> &str {
+        self.file.content()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.file.is_modified()
+    }
+
+    /// The value of the last assignment of the key in this file, with expansions resolved.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.file.get(key)
+    }
+
+    /// The value of the last assignment of the key as written, with expansions kept.
+    pub fn literal(&self, key: &str) -> Option<&str> {
+        self.file.literal(key)
+    }
+
+    /// Replaces the value of the last assignment of the key, or appends an assignment at the end of the file.
+    pub fn set(&mut self, key: &str, literal: &str) -> Result<(), ShellFileError> {
+        self.file.set(key, literal)
+    }
+
+    /// Rewrites the value of every assignment of the key as written. Returns whether a value changed.
+    pub fn edit_literals(&mut self, key: &str, edit: impl Fn(&str) -> String) -> Result<bool, ShellFileError> {
+        self.file.edit_literals(key, edit)
+    }
+}
+
+/// The GRUB defaults of a system: `/etc/default/grub` followed by `/etc/default/grub.d/*.cfg`,
+/// in the order `grub-mkconfig` sources them.
+#[derive(Debug)]
+pub struct GrubConfig {
+    files: Vec<GrubDefaults>,
+}
+
+impl GrubConfig {
+    pub fn load(root: &SystemRoot) -> Result<Self, Box<dyn Error>> {
+        let mut sources = vec![(GRUB_DEFAULTS.to_string(), root.read_to_string(GRUB_DEFAULTS)?)];
+
+        let dir = root.path(GRUB_DEFAULTS_DIR);
+        if dir.is_dir() {
+            let mut names = vec![];
+            for entry in fs::read_dir(&dir).map_err(|e| FileError::new("read directory", GRUB_DEFAULTS_DIR, e))? {
+                let name = entry.map_err(|e| FileError::new("read directory", GRUB_DEFAULTS_DIR, e))?.file_name();
+                let name = name.to_string_lossy().to_string();
+                if name.ends_with(".cfg") {
+                    names.push(name);
+                }
+            }
+            names.sort();
+            for name in names {
+                let path = format!("{}/{}", GRUB_DEFAULTS_DIR, name);
+                let content = root.read_to_string(&path)?;
+                sources.push((path, content));
+            }
+        }
+
+        Ok(GrubConfig::parse(&sources)?)
+    }
+
+    /// Parses the files in the order they are sourced.
+    pub fn parse(sources: &[(String, String)]) -> Result<Self, ShellFileError> {
+        let mut env = HashMap::new();
+        let files = sources
+            .iter()
+            .map(|(path, content)| GrubDefaults::parse(path, content, &mut env))
+            .collect::<Result<_, _>>()?;
+        Ok(GrubConfig { files })
+    }
+
+    /// The effective value of a key, i.e. its last assignment in any file.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.files.iter().rev().find_map(|file| file.get(key))
+    }
+
+    /// The kernel command line of the default boot entry, made of
+    /// `GRUB_CMDLINE_LINUX` followed by `GRUB_CMDLINE_LINUX_DEFAULT`.
+    pub fn cmdline(&self) -> String {
+        ["GRUB_CMDLINE_LINUX", "GRUB_CMDLINE_LINUX_DEFAULT"]
+            .iter()
+            .filter_map(|key| self.get(key))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+
+    /// Adds arguments to the value of a key, in the file that assigns it last,
+    /// or at the end of `/etc/default/grub` if no file assigns it.
+    pub fn add_args(&mut self, key: &str, args: &[&str]) -> Result<(), ShellFileError> {
+        if args.is_empty() {
+            return Ok(());
+        }
+
+        let index = self.files.iter().rposition(|file| file.get(key).is_some()).unwrap_or(0);
+        let file = &mut self.files[index];
+        let literal = add_missing_args(file.literal(key).unwrap_or(""), &args.join(" "));
+        file.set(key, &literal)?;
+        self.reparse_after(index)
+    }
+
+    /// Removes arguments from every assignment of a key, in every file.
+    pub fn remove_args(&mut self, key: &str, args: &[&str]) -> Result<(), ShellFileError> {
+        let remove = |current: &str| {
+            let literal = current.split_whitespace().filter(|arg| !args.contains(arg)).collect::<Vec<&str>>().join(" ");
+            // keep the spacing of values without the arguments
+            if literal.split_whitespace().eq(current.split_whitespace()) { current.to_string() } else { literal }
+        };
+        for index in 0..self.files.len() {
+            if self.files[index].edit_literals(key, remove)? {
+                self.reparse_after(index)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The files changed by the edits, to be written back.
+    pub fn modified_files(&self) -> impl Iterator<Item = &GrubDefaults> {
+        self.files.iter().filter(|file| file.is_modified())
+    }
+
+    /// Expands the files sourced after an edited one again, since they might refer to the edited values.
+    fn reparse_after(&mut self, index: usize) -> Result<(), ShellFileError> {
+        let mut env = self.files[index].file.env().clone();
+        for defaults in &mut self.files[index..] {
+            defaults.file.reparse(&mut env)?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends the required arguments that are missing from the current value.
+pub fn add_missing_args(current_value: &str, required_args: &str) -> String {
+    let mut args: Vec<&str> = current_value.split_whitespace().collect();
+
+    for arg in required_args.split_whitespace() {
+        if !args.contains(&arg) {
+            args.push(arg);
+        }
+    }
+
+    args.join(" ")
+}
+
+#[test]
+fn test_add_missing_args() {
+    let current_value = "console=ttyS0 vga=normal nofb";
+    let required_args = "console=ttyS0 vga=normal nofb nomodeset";
+
+    let new_value = add_missing_args(current_value, required_args);
+
+    assert_eq!(new_value, required_args);
+}
+
+#[test]
+fn test_add_to_empty() {
+    let current_value = "";
+    let required_args = "console=ttyS0 vga=normal nofb nomodeset";
+
+    let new_value = add_missing_args(current_value, required_args);
+
+    assert_eq!(new_value, required_args);
+}
+
+#[test]
+fn test_set_preserves_formatting() {
+    let content = "# keep me\nGRUB_CMDLINE_LINUX='quiet'   # comment\nGRUB_TIMEOUT=5";
+    let mut defaults = GrubDefaults::parse(GRUB_DEFAULTS, content, &mut HashMap::new()).unwrap();
+
+    defaults.set("GRUB_CMDLINE_LINUX", "quiet console=ttyS0").unwrap();
+    defaults.set("GRUB_TERMINAL", "serial").unwrap();
+
+    assert_eq!(
+        defaults.content(),
+        "# keep me\nGRUB_CMDLINE_LINUX='quiet console=ttyS0'   # comment\nGRUB_TIMEOUT=5\nGRUB_TERMINAL=\"serial\"\n"
+    );
+    assert!(defaults.is_modified());
+}
+
+#[test]
+fn test_add_args_edits_the_last_assignment_across_files() {
+    let mut config = GrubConfig::parse(&[
+        (GRUB_DEFAULTS.to_string(), "GRUB_CMDLINE_LINUX=\"\"\nGRUB_CMDLINE_LINUX_DEFAULT=\"quiet\"\n".to_string()),
+        (format!("{}/50-cloudimg.cfg", GRUB_DEFAULTS_DIR), "GRUB_CMDLINE_LINUX=\"$GRUB_CMDLINE_LINUX nofb\"\n".to_string()),
+    ])
+    .unwrap();
+
+    config.add_args("GRUB_CMDLINE_LINUX", &["console=ttyS0"]).unwrap();
+
+    let modified: Vec<&GrubDefaults> = config.modified_files().collect();
+    assert_eq!(modified.len(), 1);
+    assert_eq!(modified[0].content(), "GRUB_CMDLINE_LINUX=\"$GRUB_CMDLINE_LINUX nofb console=ttyS0\"\n");
+    assert_eq!(config.cmdline(), "nofb console=ttyS0 quiet");
+}
+
+#[test]
+fn test_remove_args_from_every_file() {
+    let mut config = GrubConfig::parse(&[
+        (GRUB_DEFAULTS.to_string(), "GRUB_CMDLINE_LINUX_DEFAULT=\"quiet splash\"\nGRUB_TIMEOUT=5\n".to_string()),
+        (format!("{}/50-cloudimg.cfg", GRUB_DEFAULTS_DIR), "GRUB_CMDLINE_LINUX_DEFAULT=\"$GRUB_CMDLINE_LINUX_DEFAULT console=tty1 quiet\"\n".to_string()),
+    ])
+    .unwrap();
+
+    config.remove_args("GRUB_CMDLINE_LINUX_DEFAULT", &["quiet", "splash"]).unwrap();
+
+    let modified: Vec<&str> = config.modified_files().map(GrubDefaults::content).collect();
+    assert_eq!(modified, vec![
+        "GRUB_CMDLINE_LINUX_DEFAULT=\"\"\nGRUB_TIMEOUT=5\n",
+        "GRUB_CMDLINE_LINUX_DEFAULT=\"$GRUB_CMDLINE_LINUX_DEFAULT console=tty1\"\n",
+    ]);
+    assert_eq!(config.cmdline(), "console=tty1");
+}
+
+#[test]
+fn test_remove_args_from_every_assignment() {
+    let mut config = GrubConfig::parse(&[(
+        GRUB_DEFAULTS.to_string(),
+        "GRUB_CMDLINE_LINUX=\"quiet net.ifnames=0\"\nGRUB_CMDLINE_LINUX=\"$GRUB_CMDLINE_LINUX splash\"\n".to_string(),
+    )])
+    .unwrap();
+
+    config.remove_args("GRUB_CMDLINE_LINUX", &["net.ifnames=0"]).unwrap();
+
+    assert_eq!(
+        config.modified_files().next().unwrap().content(),
+        "GRUB_CMDLINE_LINUX=\"quiet\"\nGRUB_CMDLINE_LINUX=\"$GRUB_CMDLINE_LINUX splash\"\n"
+    );
+    assert_eq!(config.cmdline(), "quiet splash");
+}
+
+#[test]
+fn test_add_args_appends_missing_key() {
+    let mut config = GrubConfig::parse(&[(GRUB_DEFAULTS.to_string(), "GRUB_TIMEOUT=5".to_string())]).unwrap();
+
+    config.add_args("GRUB_CMDLINE_LINUX", &["console=ttyS0", "nofb"]).unwrap();
+
+    assert_eq!(config.modified_files().next().unwrap().content(), "GRUB_TIMEOUT=5\nGRUB_CMDLINE_LINUX=\"console=ttyS0 nofb\"\n");
+}
@@ -0,0 +1,101 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::requirements::bootloader::{update_grub_defaults, Bootloader};
+use crate::requirements::{FixContext, SystemRoot};
+
+const EFI_DIR: &str = "/boot/efi/EFI";
+
+/// Regenerates the GRUB configuration from the GRUB defaults with `grub-mkconfig`
+/// (Debian, Ubuntu) or `grub2-mkconfig` (RHEL 7, SUSE).
+#[derive(Debug)]
+pub struct GrubMkconfig {
+    program: String,
+    /// The `grub.cfg` read by GRUB at boot, which `-o` must point to.
+    output: String,
+}
+
+impl GrubMkconfig {
+    pub fn new(program: String, output: String) -> Self {
+        GrubMkconfig { program, output }
+    }
+}
+
+impl Bootloader for GrubMkconfig {
+    fn name(&self) -> &'static str {
+        if self.program.ends_with("grub2-mkconfig") {
+            "grub2-mkconfig"
+        } else {
+            "grub-mkconfig"
+        }
+    }
+
+    fn update_args(&self, ctx: &mut FixContext, add: &[&str], remove: &[&str]) -> Result<(), Box<dyn Error>> {
+        log::info!("Editing the GRUB defaults");
+        update_grub_defaults(ctx, add, remove)?;
+
+        log::info!("Updating {}", self.output);
+        ctx.backup_file(&self.output)?;
+        ctx.run_command(&self.program, &["-o", &self.output])
+            .map_err(|e| format!("Failed to update the GRUB configuration: {}", e))?;
+        Ok(())
+    }
+}
+
+/// Finds the `grub.cfg` GRUB reads at boot. On EFI systems of RHEL 7 and SUSE it lives next
+/// to the GRUB EFI binary. RHEL 9 replaced that file by a stub loading `/boot/grub2/grub.cfg`,
+/// which must not be overwritten. On an alternate root the firmware is unknown, so an
+/// EFI configuration is used whenever the tree has one.
+pub fn output_path(root: &SystemRoot) -> String {
+    let booted_with_efi = !root.is_host() || Path::new("/sys/firmware/efi").exists();
+    if booted_with_efi {
+        if let Some(efi_cfg) = efi_grub_cfg(root) {
+            let is_stub = root.read_to_string(&efi_cfg).map(|cfg| cfg.contains("configfile")).unwrap_or(true);
+            if !is_stub {
+                return efi_cfg;
+            }
+        }
+    }
+
+    if root.path("/boot/grub2").is_dir() {
+        "/boot/grub2/grub.cfg".to_string()
+    } else {
+        "/boot/grub/grub.cfg".to_string()
+    }
+}
+
+/// The `grub.cfg` of the distribution in the EFI system partition, e.g. `/boot/efi/EFI/redhat/grub.cfg`.
+fn efi_grub_cfg(root: &SystemRoot) -> Option<String> {
+    let mut vendors: Vec<String> = fs::read_dir(root.path(EFI_DIR))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        // the fallback loader, not the distribution one
+        .filter(|vendor| !vendor.eq_ignore_ascii_case("boot"))
+        .collect();
+    vendors.sort();
+
+    vendors
+        .into_iter()
+        .map(|vendor| format!("{}/{}/grub.cfg", EFI_DIR, vendor))
+        .find(|cfg| root.path(cfg).is_file())
+}
+
+#[test]
+fn test_output_path_per_layout() {
+    let rhel7_efi = crate::tests::fixture_root("mkconfig-rhel7-efi", &[
+        ("/boot/efi/EFI/BOOT/BOOTX64.EFI", ""),
+        ("/boot/efi/EFI/redhat/grub.cfg", "menuentry 'Red Hat Enterprise Linux' {\n}\n"),
+        ("/boot/grub2/grubenv", ""),
+    ]);
+    let rhel9_efi = crate::tests::fixture_root("mkconfig-rhel9-efi", &[
+        ("/boot/efi/EFI/redhat/grub.cfg", "search --no-floppy --fs-uuid --set=dev 1234\nset prefix=($dev)/grub2\nconfigfile $prefix/grub.cfg\n"),
+        ("/boot/grub2/grubenv", ""),
+    ]);
+    let ubuntu = crate::tests::fixture_root("mkconfig-ubuntu", &[("/boot/grub/grub.cfg", "")]);
+
+    assert_eq!(output_path(&rhel7_efi), "/boot/efi/EFI/redhat/grub.cfg");
+    assert_eq!(output_path(&rhel9_efi), "/boot/grub2/grub.cfg");
+    assert_eq!(output_path(&ubuntu), "/boot/grub/grub.cfg");
+}
@@ -0,0 +1,51 @@
+use std::error::Error;
+
+use crate::requirements::bootloader::{bls, update_grub_defaults, Bootloader};
+use crate::requirements::FixContext;
+
+/// Updates the Boot Loader Specification entries with `grubby` (RHEL 8+, Rocky).
+/// The GRUB defaults are edited as well, so kernels installed later get the arguments too.
+#[derive(Debug)]
+pub struct Grubby {
+    program: String,
+}
+
+impl Grubby {
+    pub fn new(program: String) -> Self {
+        Grubby { program }
+    }
+}
+
+impl Bootloader for Grubby {
+    fn name(&self) -> &'static str {
+        "grubby"
+    }
+
+    fn update_args(&self, ctx: &mut FixContext, add: &[&str], remove: &[&str]) -> Result<(), Box<dyn Error>> {
+        log::info!("Editing the GRUB defaults");
+        update_grub_defaults(ctx, add, remove)?;
+
+        // grubby rewrites the entries, and on RHEL 8 the kernelopts of the GRUB environment
+        for entry in bls::entries(ctx.root())? {
+            ctx.backup_file(&entry.path)?;
+        }
+        for grubenv in bls::GRUBENV_PATHS {
+            if ctx.root().path(grubenv).exists() {
+                ctx.backup_file(grubenv)?;
+            }
+        }
+
+        log::info!("Updating the boot loader entries");
+        let mut grubby_args = vec!["--update-kernel=ALL".to_string()];
+        if !add.is_empty() {
+            grubby_args.push(format!("--args={}", add.join(" ")));
+        }
+        if !remove.is_empty() {
+            grubby_args.push(format!("--remove-args={}", remove.join(" ")));
+        }
+        let grubby_args: Vec<&str> = grubby_args.iter().map(String::as_str).collect();
+        ctx.run_command(&self.program, &grubby_args)
+            .map_err(|e| format!("Failed to update the boot loader entries: {}", e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use std::error::Error;
+use std::fmt::Debug;
+
+use thiserror::Error;
+
+use crate::requirements::bootloader::grub_defaults::{GrubConfig, GRUB_DEFAULTS};
+use crate::requirements::{FixContext, SystemRoot};
+
+pub use grub_mkconfig::GrubMkconfig;
+pub use grubby::Grubby;
+
+pub mod bls;
+pub mod cmdline;
+pub mod grub_defaults;
+pub mod grub_mkconfig;
+pub mod grubby;
+
+#[derive(Error, Debug)]
+pub enum BootloaderError {
+    #[error("No supported bootloader tooling found, looked for grubby with boot loader entries, grub2-mkconfig and grub-mkconfig")]
+    NotFound,
+}
+
+/// The distribution tooling that turns the configured kernel arguments into boot entries.
+pub trait Bootloader: Debug {
+    fn name(&self) -> &'static str;
+    /// Adds and removes kernel arguments of every installed kernel, and of the kernels installed later.
+    /// Every change must go through the context, so it can be previewed and rolled back.
+    fn update_args(&self, ctx: &mut FixContext, add: &[&str], remove: &[&str]) -> Result<(), Box<dyn Error>>;
+}
+
+/// Detects the bootloader tooling of the target system: `grubby` when the kernels are
+/// described by Boot Loader Specification entries (RHEL 8+, Rocky), otherwise
+/// `grub2-mkconfig` (RHEL 7, SUSE) or `grub-mkconfig` (Debian, Ubuntu).
+pub fn detect(root: &SystemRoot) -> Result<Box<dyn Bootloader>, BootloaderError> {
+    if bls::has_entries(root) {
+        if let Some(program) = root.find_program("grubby") {
+            return Ok(Box::new(Grubby::new(program)));
+        }
+    }
+
+    ["grub2-mkconfig", "grub-mkconfig"]
+        .iter()
+        .find_map(|name| root.find_program(name))
+        .map(|program| Box::new(GrubMkconfig::new(program, grub_mkconfig::output_path(root))) as Box<dyn Bootloader>)
+        .ok_or(BootloaderError::NotFound)
+}
+
+/// The kernel command line the next boot uses: the options of the default boot loader
+/// entry when the system uses them, otherwise the command line built from the GRUB defaults.
+pub fn configured_cmdline(root: &SystemRoot) -> Result<String, Box<dyn Error>> {
+    if let Some(options) = bls::default_options(root)? {
+        return Ok(options);
+    }
+    Ok(GrubConfig::load(root)?.cmdline())
+}
+
+/// Adds arguments to `GRUB_CMDLINE_LINUX` and removes arguments from both `GRUB_CMDLINE_LINUX`
+/// and `GRUB_CMDLINE_LINUX_DEFAULT` in the GRUB defaults, if the system has them.
+fn update_grub_defaults(ctx: &mut FixContext, add: &[&str], remove: &[&str]) -> Result<(), Box<dyn Error>> {
+    if !ctx.root().path(GRUB_DEFAULTS).exists() {
+        return Ok(());
+    }
+
+    let mut config = GrubConfig::load(ctx.root())?;
+    for key in ["GRUB_CMDLINE_LINUX", "GRUB_CMDLINE_LINUX_DEFAULT"] {
+        config.remove_args(key, remove)?;
+    }
+    config.add_args("GRUB_CMDLINE_LINUX", add)?;
+    for file in config.modified_files() {
+        ctx.write_file(file.path(), file.content())?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_detect_grubby_with_bls_entries() {
+    let root = crate::tests::fixture_root("bootloader-grubby", &[
+        ("/usr/sbin/grubby", ""),
+        ("/usr/sbin/grub2-mkconfig", ""),
+        ("/boot/loader/entries/abc-5.14.0-362.el9.x86_64.conf", "version 5.14.0-362.el9.x86_64\noptions root=/dev/vda1\n"),
+    ]);
+
+    assert_eq!(detect(&root).unwrap().name(), "grubby");
+    assert_eq!(configured_cmdline(&root).unwrap(), "root=/dev/vda1");
+}
+
+#[test]
+fn test_detect_grub_mkconfig() {
+    let root = crate::tests::fixture_root("bootloader-mkconfig", &[("/usr/sbin/grub-mkconfig", "")]);
+
+    assert_eq!(detect(&root).unwrap().name(), "grub-mkconfig");
+    assert!(detect(&crate::tests::fixture_root("bootloader-none", &[])).is_err());
+}
@@ -1,10 +1,10 @@
 use std::any::Any;
 use std::error::Error;
-use std::ops::Deref;
 
+use serde_json::{json, Value};
 use sysinfo::{DiskExt, SystemExt};
 
-use crate::requirements::{CheckResult, Requirement};
+use crate::requirements::{CheckResult, Requirement, Severity, SystemRoot};
 
 struct BootDiskSizeCheckResult {
     pub is_supported: bool,
@@ -16,35 +16,77 @@ impl CheckResult for BootDiskSizeCheckResult {
         self.is_supported
     }
     fn log(&self)  {
-        let result = self.deref();
-        if result.is_supported {
-            log::info!("Boot disk size is supported");
+        if self.is_supported {
+            log::info!("{}", self.message());
         } else {
-            log::warn!("Boot disk size is not supported, limit is 250 GB, and the actual size is {} GB", result.acual_size_in_gb);
+            log::warn!("{}", self.message());
         }
     }
+    fn message(&self) -> String {
+        if self.is_supported {
+            "Boot disk size is supported".to_string()
+        } else {
+            format!("Boot disk size is not supported, limit is 250 GB, and the actual size is {} GB", self.acual_size_in_gb)
+        }
+    }
+    fn details(&self) -> Value {
+        json!({
+            "actual_size_in_gb": self.acual_size_in_gb,
+            "limit_in_gb": 250,
+        })
+    }
+    fn severity(&self) -> Severity {
+        if self.is_supported {
+            Severity::Info
+        } else {
+            Severity::Blocker
+        }
+    }
+    fn remediation(&self) -> Option<String> {
+        if self.is_supported {
+            None
+        } else {
+            Some("Reduce the boot disk to less than 250 GB, e.g. by moving data to a separate data volume.".to_string())
+        }
+    }
+    fn documentation_url(&self) -> &'static str {
+        "https://cloud.ibm.com/docs/vpc?topic=vpc-importing-custom-images-vpc"
+    }
 }
 
 #[derive(Debug)]
 pub struct BootDiskSizeRequirement;
 
 impl Requirement for BootDiskSizeRequirement {
-    fn check(&self) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
-        let result = check_boot_disk_size();
+    fn name(&self) -> &'static str {
+        "boot-disk-size"
+    }
+    fn check(&self, root: &SystemRoot) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
+        let result = check_boot_disk_size(root)?;
         result.log();
         Ok(Box::new(result))
     }
 }
 
-fn check_boot_disk_size() -> BootDiskSizeCheckResult {
+/// Sums the size of the disks of the system. On an alternate root only the
+/// disks mounted at or below that root are taken into account, and a root without
+/// any, e.g. an extracted image, cannot be evaluated.
+fn check_boot_disk_size(root: &SystemRoot) -> Result<BootDiskSizeCheckResult, Box<dyn Error>> {
     let mut sys = sysinfo::System::new();
+    sys.refresh_disks_list();
     sys.refresh_disks();
 
-    let disk_sum = sys
+    let root_path = root.root().canonicalize().unwrap_or_else(|_| root.root().to_path_buf());
+
+    let disks: Vec<_> = sys
         .disks()
         .iter()
-        .map(|disk| disk.total_space())
-        .sum::<u64>();
+        .filter(|disk| root.is_host() || disk.mount_point().starts_with(&root_path))
+        .collect();
+    if disks.is_empty() {
+        return Err(format!("No disk is mounted at or below {}, the boot disk size is unknown", root_path.display()).into());
+    }
+    let disk_sum = disks.iter().map(|disk| disk.total_space()).sum::<u64>();
 
     let disk_size_gb = disk_sum / 1024 / 1024 / 1024; // Convert to GB
 
@@ -54,8 +96,15 @@ fn check_boot_disk_size() -> BootDiskSizeCheckResult {
         );
     }
 
-    BootDiskSizeCheckResult {
+    Ok(BootDiskSizeCheckResult {
         is_supported: disk_size_gb < 250,
         acual_size_in_gb: disk_size_gb,
-    }
+    })
+}
+
+#[test]
+fn test_boot_disk_size_of_an_unmounted_root() {
+    let root = crate::tests::fixture_root("boot-disk-unmounted", &[("/etc/hostname", "image\n")]);
+
+    assert!(check_boot_disk_size(&root).is_err());
 }
@@ -1,9 +1,10 @@
 use std::any::Any;
 use std::error::Error;
-use std::ops::Deref;
 use std::process::Command;
 
-use crate::requirements::{CheckResult, Requirement};
+use serde_json::{json, Value};
+
+use crate::requirements::{CheckResult, Requirement, Severity, SystemRoot};
 
 struct CloudInitCheckResult {
     pub is_installed: bool,
@@ -14,11 +15,34 @@ impl CheckResult for CloudInitCheckResult {
         self.is_installed
     }
     fn log(&self) -> () {
-        let result = self.deref();
-        if result.is_installed {
-            log::info!("Cloud-init is installed");
+        if self.is_installed {
+            log::info!("{}", self.message());
+        } else {
+            log::warn!("{}", self.message());
+        }
+    }
+    fn message(&self) -> String {
+        if self.is_installed {
+            "Cloud-init is installed".to_string()
+        } else {
+            "Cloud-init is not installed".to_string()
+        }
+    }
+    fn details(&self) -> Value {
+        json!({ "installed": self.is_installed })
+    }
+    fn severity(&self) -> Severity {
+        if self.is_installed {
+            Severity::Info
+        } else {
+            Severity::Warning
+        }
+    }
+    fn remediation(&self) -> Option<String> {
+        if self.is_installed {
+            None
         } else {
-            log::warn!("Cloud-init is not installed");
+            Some("Install cloud-init with the package manager of the distribution, e.g. `apt install cloud-init` or `dnf install cloud-init`.".to_string())
         }
     }
 }
@@ -27,14 +51,25 @@ impl CheckResult for CloudInitCheckResult {
 pub struct CloudInitRequirement;
 
 impl Requirement for CloudInitRequirement {
-    fn check(&self) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
-        let result = check_cloud_init()?;
+    fn name(&self) -> &'static str {
+        "cloud-init"
+    }
+    fn check(&self, root: &SystemRoot) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
+        let result = check_cloud_init(root)?;
         result.log();
         Ok(Box::new(result))
     }
 }
 
-fn check_cloud_init() -> Result<CloudInitCheckResult, Box<dyn Error>> {
+/// On the running host it runs `cloud-init --version`, on an alternate root
+/// it looks for the `cloud-init` executable inside the tree.
+fn check_cloud_init(root: &SystemRoot) -> Result<CloudInitCheckResult, Box<dyn Error>> {
+    if !root.is_host() {
+        return Ok(CloudInitCheckResult {
+            is_installed: root.find_program("cloud-init").is_some()
+        });
+    }
+
     let mut cmd = Command::new("cloud-init");
     cmd.arg("--version");
 
@@ -1,12 +1,40 @@
 use std::any::Any;
 use std::error::Error;
-use std::fs;
-use std::ops::Deref;
 
-use crate::requirements::{CheckResult, Requirement};
+use serde_json::{json, Value};
+
+use crate::requirements::network::{self, Addressing, Interface, InterfaceConfig};
+use crate::requirements::{CheckResult, FixContext, Requirement, Severity, SystemRoot};
 
 struct DhcpEnabledCheckResult {
     pub is_enabled: bool,
+    /// The interface of the default route, or the one guessed from the configuration on an alternate root.
+    primary: Option<Interface>,
+    /// The configurations that apply to the primary interface.
+    primary_configs: Vec<InterfaceConfig>,
+    /// Set when no configuration applies, and NetworkManager creates a DHCP connection on its own.
+    network_manager_default: bool,
+    configs: Vec<InterfaceConfig>,
+}
+
+impl DhcpEnabledCheckResult {
+    fn problem(&self) -> String {
+        let primary = match &self.primary {
+            Some(primary) => primary,
+            None => return "No primary network interface found".to_string(),
+        };
+        if self.primary_configs.is_empty() {
+            return format!("No configuration found for {}", primary.name);
+        }
+
+        let configs: Vec<String> = self
+            .primary_configs
+            .iter()
+            .filter(|config| config.addressing != Addressing::Dhcp)
+            .map(|config| format!("{} in {}", config.addressing, config.path))
+            .collect();
+        format!("{} is configured {}", primary.name, configs.join(", "))
+    }
 }
 
 impl CheckResult for DhcpEnabledCheckResult {
@@ -14,11 +42,42 @@ impl CheckResult for DhcpEnabledCheckResult {
         self.is_enabled
     }
     fn log(&self) -> () {
-        let result = self.deref();
-        if result.is_enabled {
-            log::info!("DHCP is enabled");
+        if self.is_enabled {
+            log::info!("{}", self.message());
         } else {
-            log::warn!("DHCP is not enabled");
+            log::warn!("{}", self.message());
+        }
+    }
+    fn message(&self) -> String {
+        match (&self.primary, self.is_enabled) {
+            (Some(primary), true) if self.network_manager_default => {
+                format!("DHCP is enabled on {} by the default connection of NetworkManager", primary.name)
+            }
+            (Some(primary), true) => format!("DHCP is enabled on {}", primary.name),
+            _ => format!("DHCP is not enabled. {}", self.problem()),
+        }
+    }
+    fn details(&self) -> Value {
+        json!({
+            "dhcp_enabled": self.is_enabled,
+            "primary_interface": self.primary,
+            "primary_configs": self.primary_configs,
+            "network_manager_default": self.network_manager_default,
+            "configs": self.configs,
+        })
+    }
+    fn severity(&self) -> Severity {
+        if self.is_enabled {
+            Severity::Info
+        } else {
+            Severity::Blocker
+        }
+    }
+    fn remediation(&self) -> Option<String> {
+        if self.is_enabled {
+            None
+        } else {
+            Some("Configure the primary network interface to obtain its address through DHCP, without a static gateway or DNS servers, or apply the fix of this requirement.".to_string())
         }
     }
 }
@@ -27,39 +86,112 @@ impl CheckResult for DhcpEnabledCheckResult {
 pub struct DhcpEnabledRequirement;
 
 impl Requirement for DhcpEnabledRequirement {
-    fn check(&self) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
-        let result = check_dhcp_enabled()?;
+    fn name(&self) -> &'static str {
+        "dhcp-enabled"
+    }
+    fn check(&self, root: &SystemRoot) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
+        let result = check_dhcp_enabled(root)?;
         result.log();
         Ok(Box::new(result))
     }
+    fn fix(&self, ctx: &mut FixContext) -> Option<Result<bool, Box<dyn Error>>> {
+        Some(fix_dhcp_enabled(ctx))
+    }
+    fn fixable(&self) -> bool {
+        true
+    }
 }
 
-/// Checks if DHCP is enabled by looking into the configuration files
-/// located in the `/etc/netplan/` directory. If the string `dhcp4: true`
-/// is found in one of the files, it assumes DHCP is enabled.
-fn check_dhcp_enabled() -> Result<DhcpEnabledCheckResult, Box<dyn Error>> {
-    let directory = "/etc/netplan/";
+/// Checks that the primary interface gets its IPv4 address through DHCP, in the configuration
+/// of every network backend found: netplan, NetworkManager, `ifcfg-*` files, ifupdown,
+/// systemd-networkd and wicked. Every configuration applying to the interface must use DHCP.
+fn check_dhcp_enabled(root: &SystemRoot) -> Result<DhcpEnabledCheckResult, Box<dyn Error>> {
+    let configs = network::inspect(root)?;
+    let primary = network::primary_interface(root, &configs);
+    let primary_configs: Vec<InterfaceConfig> = match &primary {
+        Some(primary) => configs.iter().filter(|config| config.applies_to(primary)).cloned().collect(),
+        None => vec![],
+    };
 
-    // read the directory
-    let config_files = fs::read_dir(directory)
-        .map_err(|_| format!("Failed to read directory: {}", directory))?;
+    let network_manager_default = primary.is_some() && primary_configs.is_empty() && root.find_program("NetworkManager").is_some();
+    let is_enabled = network_manager_default
+        || (!primary_configs.is_empty() && primary_configs.iter().all(|config| config.addressing == Addressing::Dhcp));
 
-    let mut dhcp_enabled = false;
+    Ok(DhcpEnabledCheckResult { is_enabled, primary, primary_configs, network_manager_default, configs })
+}
 
-    for file in config_files {
-        // read the file
-        let file = file.map_err(|_| "Failed to read file")?;
+/// Rewrites the configurations of the primary interface that do not use DHCP, in the format
+/// of their backend. The running network is left alone, so a remote session is not cut:
+/// the new configuration is used from the next boot on.
+fn fix_dhcp_enabled(ctx: &mut FixContext) -> Result<bool, Box<dyn Error>> {
+    let result = check_dhcp_enabled(ctx.root())?;
+    if result.primary_configs.is_empty() {
+        return Err(format!("Cannot switch to DHCP: {}", result.problem()).into());
+    }
 
-        // read the file content
-        let content = fs::read_to_string(file.path())
-            .map_err(|_| format!("Failed to read file content: {:?}", file.path()))?;
+    for config in result.primary_configs.iter().filter(|config| config.addressing != Addressing::Dhcp) {
+        log::info!("Switching {} to DHCP in {}", config.id, config.path);
+        network::convert_to_dhcp(ctx, config)?;
+    }
 
-        if content.contains("dhcp4: true") {
-            dhcp_enabled = true;
-            break;
-        }
+    if !ctx.is_dry_run() {
+        log::warn!("The network configuration is applied at the next boot.");
     }
+    Ok(true)
+}
+
+#[test]
+fn test_dhcp_enabled_in_root() {
+    let root = crate::tests::fixture_root("dhcp-enabled", &[
+        ("/etc/netplan/50-cloud-init.yaml", "network:\n  ethernets:\n    eth0:\n      dhcp4: true\n"),
+    ]);
 
-    Ok(DhcpEnabledCheckResult { is_enabled: dhcp_enabled })
+    assert!(check_dhcp_enabled(&root).unwrap().is_enabled);
 }
 
+#[test]
+fn test_dhcp_disabled_in_root() {
+    let root = crate::tests::fixture_root("dhcp-disabled", &[
+        ("/etc/netplan/01-static.yaml", "network:\n  ethernets:\n    eth0:\n      addresses: [10.0.0.5/24]\n"),
+    ]);
+
+    assert!(!check_dhcp_enabled(&root).unwrap().is_enabled);
+}
+
+#[test]
+fn test_static_ifcfg_of_primary_interface() {
+    let root = crate::tests::fixture_root("dhcp-ifcfg", &[
+        ("/etc/sysconfig/network-scripts/ifcfg-lo", "DEVICE=lo\nIPADDR=127.0.0.1\n"),
+        ("/etc/sysconfig/network-scripts/ifcfg-eth1", "DEVICE=eth1\nBOOTPROTO=dhcp\n"),
+        ("/etc/sysconfig/network-scripts/ifcfg-eth0", "DEVICE=eth0\nBOOTPROTO=none\nIPADDR=10.0.0.5\nGATEWAY=10.0.0.1\n"),
+    ]);
+
+    let result = check_dhcp_enabled(&root).unwrap();
+
+    assert!(!result.is_enabled);
+    assert_eq!(result.primary.unwrap().name, "eth0");
+    assert_eq!(result.primary_configs.len(), 1);
+    assert_eq!(result.configs.len(), 2);
+}
+
+#[test]
+fn test_no_network_configuration() {
+    let root = crate::tests::fixture_root("dhcp-none", &[]);
+
+    let result = check_dhcp_enabled(&root).unwrap();
+
+    assert!(!result.is_enabled);
+    assert_eq!(result.message(), "DHCP is not enabled. No primary network interface found");
+}
+
+#[test]
+fn test_fix_switches_primary_interface_to_dhcp() {
+    let root = crate::tests::fixture_root("dhcp-fix", &[
+        ("/etc/network/interfaces", "auto lo\niface lo inet loopback\n\nauto ens3\niface ens3 inet static\n    address 192.168.1.10/24\n    gateway 192.168.1.1\n"),
+    ]);
+
+    fix_dhcp_enabled(&mut FixContext::new(&root, false)).unwrap();
+
+    assert!(check_dhcp_enabled(&root).unwrap().is_enabled);
+    assert_eq!(root.read_to_string("/etc/network/interfaces").unwrap(), "auto lo\niface lo inet loopback\n\nauto ens3\niface ens3 inet dhcp\n");
+}
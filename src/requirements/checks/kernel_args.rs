@@ -1,13 +1,75 @@
 use std::any::Any;
 use std::error::Error;
-use std::fs;
-use std::process::Command;
 
-use crate::requirements::{CheckResult, Requirement};
+use serde_json::{json, Value};
+
+use crate::requirements::{CheckResult, FixContext, Requirement, Severity, SystemRoot};
+use crate::requirements::bootloader;
+use crate::requirements::bootloader::cmdline;
+
+const REQUIRED_ARGS: [&str; 4] = ["nomodeset", "nofb", "vga=normal", "console=ttyS0"];
+/// Arguments that hide the boot messages from the serial console.
+const FORBIDDEN_ARGS: [&str; 2] = ["quiet", "splash"];
+const SERIAL_CONSOLE: &str = "console=ttyS0";
 
 struct KernelCheckResult {
     pub is_supported: bool,
+    /// Required arguments missing from the configuration of the next boot, and from the running kernel.
     pub missing_args: Vec<String>,
+    /// Conflicting arguments in the configuration of the next boot.
+    pub conflicting_args: Vec<String>,
+    /// Required arguments of the running kernel that are not configured, so the next boot loses them.
+    pub unconfigured_args: Vec<String>,
+    /// Required arguments that are configured, but only active after a reboot.
+    pub pending_args: Vec<String>,
+    /// Conflicting arguments of the running kernel that are no longer configured.
+    pub pending_removals: Vec<String>,
+}
+
+impl KernelCheckResult {
+    /// Compares the arguments configured for the next boot with the ones of the running kernel,
+    /// which are only known on the host.
+    fn new(configured: &[String], running: Option<&[String]>) -> Self {
+        let configured_missing = missing_args(configured);
+        let configured_conflicting = conflicting_args(configured);
+        let (running_missing, running_conflicting) = match running {
+            Some(running) => (missing_args(running), conflicting_args(running)),
+            None => (configured_missing.clone(), configured_conflicting.clone()),
+        };
+
+        let mut result = KernelCheckResult {
+            is_supported: false,
+            missing_args: strings(configured_missing.iter().filter(|&arg| running_missing.contains(arg))),
+            unconfigured_args: strings(configured_missing.iter().filter(|&arg| !running_missing.contains(arg))),
+            pending_args: strings(running_missing.iter().filter(|&arg| !configured_missing.contains(arg))),
+            pending_removals: strings(running_conflicting.iter().filter(|&arg| !configured_conflicting.contains(arg))),
+            conflicting_args: configured_conflicting,
+        };
+        result.is_supported = result.problems().is_empty();
+        result
+    }
+
+    /// True when the configuration is right, and only a reboot is needed to make it active.
+    fn is_pending_reboot(&self) -> bool {
+        !self.is_supported
+            && self.missing_args.is_empty()
+            && self.unconfigured_args.is_empty()
+            && self.conflicting_args.is_empty()
+    }
+
+    fn problems(&self) -> Vec<String> {
+        [
+            ("Missing arguments", &self.missing_args),
+            ("Active but not configured, lost on the next boot", &self.unconfigured_args),
+            ("Conflicting arguments", &self.conflicting_args),
+            ("Configured, active after a reboot", &self.pending_args),
+            ("Removed from the configuration, active until a reboot", &self.pending_removals),
+        ]
+        .iter()
+        .filter(|(_, args)| !args.is_empty())
+        .map(|(label, args)| format!("{}: {}", label, args.join(", ")))
+        .collect()
+    }
 }
 
 impl CheckResult for KernelCheckResult {
@@ -16,17 +78,62 @@ impl CheckResult for KernelCheckResult {
     }
 
     fn log(&self) -> () {
-        let result = self.clone();
-        if result.is_supported {
-            log::info!("Kernel arguments are supported");
+        if self.is_supported {
+            log::info!("{}", self.message());
         } else {
             log::warn!("Kernel arguments are not supported");
-            log::warn!(
-                "Missing arguments: {}",
-                result.missing_args.join(", ")
-            );
+            for problem in self.problems() {
+                log::warn!("{}", problem);
+            }
+        }
+    }
+
+    fn message(&self) -> String {
+        if self.is_supported {
+            "Kernel arguments are supported".to_string()
+        } else {
+            format!("Kernel arguments are not supported. {}", self.problems().join(". "))
+        }
+    }
+
+    fn details(&self) -> Value {
+        json!({
+            "missing_args": self.missing_args,
+            "conflicting_args": self.conflicting_args,
+            "unconfigured_args": self.unconfigured_args,
+            "pending_args": self.pending_args,
+            "pending_removals": self.pending_removals,
+        })
+    }
+    fn severity(&self) -> Severity {
+        if self.is_supported {
+            Severity::Info
+        } else {
+            Severity::Warning
         }
     }
+    fn remediation(&self) -> Option<String> {
+        if self.is_supported {
+            return None;
+        }
+        if self.is_pending_reboot() {
+            return Some("Reboot the system to activate the configured kernel arguments.".to_string());
+        }
+
+        let mut steps = vec![];
+        let add: Vec<&str> = self.missing_args.iter().chain(&self.unconfigured_args).map(String::as_str).collect();
+        if !add.is_empty() {
+            steps.push(format!("add {}", add.join(" ")));
+        }
+        if !self.conflicting_args.is_empty() {
+            steps.push(format!("remove {}", self.conflicting_args.join(" ")));
+        }
+        Some(format!("In GRUB_CMDLINE_LINUX and GRUB_CMDLINE_LINUX_DEFAULT of /etc/default/grub, {}, then regenerate the GRUB configuration, or apply the fix of this requirement.", steps.join(", ")))
+    }
+}
+
+fn strings<'a, T: AsRef<str> + 'a>(args: impl Iterator<Item = &'a T>) -> Vec<String> {
+    args.map(|arg| arg.as_ref().to_string()).collect()
 }
 
 #[derive(Debug)]
@@ -34,124 +141,179 @@ pub struct KernelArgsRequirement;
 
 impl Requirement for KernelArgsRequirement {
     // type CheckResultType = KernelCheckResult;
-    fn check(&self) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
-        let result = check_kernel_args()?;
+    fn name(&self) -> &'static str {
+        "kernel-args"
+    }
+    fn check(&self, root: &SystemRoot) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
+        let result = check_kernel_args(root)?;
         result.log();
         Ok(Box::new(result))
     }
-    fn fix(&self) -> Option<Result<(), Box<dyn Error>>> {
-        Some(fix_kernel_args())
+    fn fix(&self, ctx: &mut FixContext) -> Option<Result<bool, Box<dyn Error>>> {
+        Some(fix_kernel_args(ctx))
     }
     fn fixable(&self) -> bool {
         true
     }
+    fn takes_effect_after_reboot(&self, root: &SystemRoot) -> bool {
+        root.is_host() && check_kernel_args(root).map(|result| result.is_pending_reboot()).unwrap_or(false)
+    }
 }
 
-fn check_kernel_args() -> Result<KernelCheckResult, Box<dyn Error>> {
-    let required_args = ["nomodeset", "nofb", "vga=normal", "console=ttyS0"];
-    let cmdline = crate::utils::read_file_to_string("/proc/cmdline");
+/// Checks the arguments configured for the next boot. On the running host they are also
+/// compared with `/proc/cmdline`, so arguments that are only active until the next boot,
+/// or only after it, are reported.
+fn check_kernel_args(root: &SystemRoot) -> Result<KernelCheckResult, Box<dyn Error>> {
+    let configured = cmdline::parse(&bootloader::configured_cmdline(root)?);
+    let running = if root.is_host() {
+        Some(cmdline::parse(&crate::utils::read_file_to_string("/proc/cmdline")?))
+    } else {
+        None
+    };
+
+    Ok(KernelCheckResult::new(&configured, running.as_deref()))
+}
 
-    let missing_args: Vec<&str> = required_args
+fn missing_args(args: &[String]) -> Vec<&'static str> {
+    REQUIRED_ARGS
         .iter()
-        .filter(|&arg| !cmdline.contains(arg))
+        .filter(|&required| !args.iter().any(|arg| arg == required))
         .cloned()
-        .collect();
+        .collect()
+}
+
+/// Finds the arguments to remove: the forbidden ones, and the other consoles that come after
+/// the serial console, since the last `console=` becomes `/dev/console`. When the serial console
+/// is missing, every other console conflicts, as the added one might not end up last.
+fn conflicting_args(args: &[String]) -> Vec<String> {
+    let last_serial = args.iter().rposition(|arg| arg == SERIAL_CONSOLE);
 
-    Ok(KernelCheckResult {
-        is_supported: missing_args.is_empty(),
-        missing_args: missing_args.iter().map(|&arg| arg.to_string()).collect(),
-    })
+    let mut conflicting: Vec<String> = vec![];
+    for (index, arg) in args.iter().enumerate() {
+        let steals_console = arg.starts_with("console=")
+            && arg != SERIAL_CONSOLE
+            && last_serial.is_none_or(|serial| index > serial);
+
+        if (FORBIDDEN_ARGS.contains(&arg.as_str()) || steals_console) && !conflicting.contains(arg) {
+            conflicting.push(arg.clone());
+        }
+    }
+    conflicting
 }
 
-fn fix_kernel_args() -> Result<(), Box<dyn Error>> {
-    let required_args = "console=ttyS0 vga=normal nofb nomodeset";
+/// Adds the missing arguments and removes the conflicting ones with the bootloader tooling of the distribution.
+fn fix_kernel_args(ctx: &mut FixContext) -> Result<bool, Box<dyn Error>> {
+    let args = cmdline::parse(&bootloader::configured_cmdline(ctx.root())?);
+    let missing_args = missing_args(&args);
+    let conflicting_args = conflicting_args(&args);
+    let conflicting_args: Vec<&str> = conflicting_args.iter().map(String::as_str).collect();
 
-    log::info!("Backing up grub");
-    fs::copy("/etc/default/grub", "/tmp/grub")?;
+    // the configuration is already right, only a reboot activates it
+    if missing_args.is_empty() && conflicting_args.is_empty() {
+        log::info!("The kernel arguments are already configured, reboot the system to activate them.");
+        return Ok(false);
+    }
 
-    log::info!("Editing grub file");
-    /// Finds the line that starts with `GRUB_CMDLINE_LINUX=`
-    /// gets the actual value
-    /// adds the missing args
-    /// panics if the line is not found
-    let grub_content = crate::utils::read_file_to_string("/etc/default/grub");
+    let bootloader = bootloader::detect(ctx.root())?;
+    log::info!("Updating the kernel arguments with {}", bootloader.name());
+    bootloader.update_args(ctx, &missing_args, &conflicting_args)?;
 
-    let grub_content = add_required_args_to_kernel(required_args, grub_content);
+    if !ctx.is_dry_run() {
+        log::warn!("Please reboot the system to apply the kernel changes.");
+    }
 
-    fs::write("/etc/default/grub", grub_content)?;
+    Ok(true)
+}
 
-    log::info!("Backing up grub config");
-    fs::copy("/boot/grub/grub.cfg", "/tmp/grub_bkp.cfg")?;
+#[test]
+fn test_kernel_args_in_root_use_grub_defaults() {
+    let root = crate::tests::fixture_root("kernel-args", &[
+        ("/etc/default/grub", "GRUB_DEFAULT=0\nGRUB_CMDLINE_LINUX_DEFAULT=\"nomodeset nofb\"\nGRUB_CMDLINE_LINUX=\"vga=normal\"\n"),
+    ]);
 
-    log::info!("Updating grub config");
-    let output = Command::new("update-grub")
-        .output()?;
+    let result = check_kernel_args(&root).unwrap();
 
-    if !output.status.success() {
-        // todo what if this processes fails? maybe an saga pattern to rollback?
-        let err_msg = String::from_utf8_lossy(&output.stderr);
-        log::error!("Failed to update grub: {}", err_msg);
-        return Err("Failed to update grub".into());
-    }
+    assert_eq!(result.missing_args, vec!["console=ttyS0"]);
+}
 
-    log::warn!("Please reboot the system to apply the kernel changes.");
+#[test]
+fn test_fix_leaves_a_configuration_pending_reboot_alone() {
+    let root = crate::tests::fixture_root("kernel-args-pending", &[
+        ("/etc/default/grub", "GRUB_CMDLINE_LINUX=\"nomodeset nofb vga=normal console=ttyS0\"\n"),
+    ]);
+    let mut ctx = FixContext::new(&root, false);
 
-    Ok(())
+    assert!(!fix_kernel_args(&mut ctx).unwrap());
+    assert!(ctx.changes().is_empty());
 }
 
-fn add_required_args_to_kernel(required_args: &str, grub_content: String) -> String {
-    let new_grub_content: Vec<String> = grub_content
-        .lines()
-        .map(|line| {
-            if line.starts_with("GRUB_CMDLINE_LINUX=") {
-                let grub_cmdline_current_value = line
-                    .split('=')
-                    .nth(1)
-                    .unwrap()
-                    .trim_matches('"');
-                let grub_cmdline_new_value = add_missing_args(grub_cmdline_current_value, required_args);
-
-                format!("GRUB_CMDLINE_LINUX=\"{}\"", grub_cmdline_new_value)
-            } else {
-                line.to_string()
-            }
-        })
-        .collect();
+#[test]
+fn test_args_match_exactly_and_conflicts_are_found() {
+    let args = cmdline::parse("root=/dev/vda1 console=tty0 console=ttyS01 nofbdev nomodeset vga=normal quiet console=ttyS0 console=tty1");
 
-    new_grub_content.join("\n")
+    assert_eq!(missing_args(&args), vec!["nofb"]);
+    assert_eq!(conflicting_args(&args), vec!["quiet", "console=tty1"]);
+    assert_eq!(conflicting_args(&cmdline::parse("console=tty0 splash")), vec!["console=tty0", "splash"]);
 }
 
-// tests
 #[test]
-fn test_add_missing_args() {
-    let current_value = "console=ttyS0 vga=normal nofb";
-    let required_args = "console=ttyS0 vga=normal nofb nomodeset";
+fn test_running_and_configured_args_are_compared() {
+    let configured = cmdline::parse("root=/dev/vda1 nomodeset nofb console=ttyS0");
+    let running = cmdline::parse("root=/dev/vda1 nomodeset vga=normal console=ttyS0 quiet");
 
-    let new_value = add_missing_args(current_value, required_args);
+    let result = KernelCheckResult::new(&configured, Some(&running));
 
-    assert_eq!(new_value, required_args);
+    assert_eq!(result.unconfigured_args, vec!["vga=normal"]);
+    assert_eq!(result.pending_args, vec!["nofb"]);
+    assert_eq!(result.pending_removals, vec!["quiet"]);
+    assert!(result.missing_args.is_empty() && result.conflicting_args.is_empty());
+    assert!(!result.is_pending_reboot());
+
+    let configured = cmdline::parse("root=/dev/vda1 nomodeset nofb vga=normal console=ttyS0");
+    let result = KernelCheckResult::new(&configured, Some(&running));
+
+    assert!(!result.passed());
+    assert!(result.is_pending_reboot());
 }
 
 #[test]
-fn test_add_to_empty() {
-    let current_value = "";
-    let required_args = "console=ttyS0 vga=normal nofb nomodeset";
+fn test_fix_kernel_args_keeps_existing_values() {
+    use crate::requirements::fix::Change;
+
+    let root = crate::tests::fixture_root("kernel-args-fix", &[
+        ("/etc/default/grub", "GRUB_DEFAULT=0\nGRUB_CMDLINE_LINUX=\"console=ttyS0 root=UUID=1234\"\n"),
+        ("/usr/sbin/grub-mkconfig", ""),
+        ("/boot/grub/grub.cfg", ""),
+    ]);
+    let mut ctx = FixContext::new(&root, true);
 
-    let new_value = add_missing_args(current_value, required_args);
+    fix_kernel_args(&mut ctx).unwrap();
 
-    assert_eq!(new_value, required_args);
+    match ctx.changes() {
+        [Change::WriteFile { diff, .. }, Change::RunCommand { command }] => {
+            assert!(diff.contains("+GRUB_CMDLINE_LINUX=\"console=ttyS0 root=UUID=1234 nomodeset nofb vga=normal\"\n"));
+            assert_eq!(command, "/usr/sbin/grub-mkconfig -o /boot/grub/grub.cfg");
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
 }
 
-fn add_missing_args(current_value: &str, required_args: &str) -> String {
-    let current_value = current_value.trim();
-    let mut args: Vec<&str> = current_value.split(' ').collect();
-    let required_args: Vec<&str> = required_args.split(' ').collect();
+#[test]
+fn test_fix_kernel_args_with_grubby() {
+    use crate::requirements::fix::Change;
+
+    let root = crate::tests::fixture_root("kernel-args-grubby", &[
+        ("/usr/sbin/grubby", ""),
+        ("/boot/loader/entries/abc-5.14.0-362.el9.x86_64.conf", "version 5.14.0-362.el9.x86_64\noptions root=/dev/vda1 console=ttyS0 nofb quiet\n"),
+    ]);
+    let mut ctx = FixContext::new(&root, true);
 
-    for arg in required_args {
-        if !args.contains(&arg) {
-            args.push(arg);
+    fix_kernel_args(&mut ctx).unwrap();
+
+    match ctx.changes() {
+        [Change::RunCommand { command }] => {
+            assert_eq!(command, "/usr/sbin/grubby --update-kernel=ALL --args=nomodeset vga=normal --remove-args=quiet");
         }
+        changes => panic!("unexpected changes: {:?}", changes),
     }
-
-    args.join(" ")
-}
\ No newline at end of file
+}
@@ -0,0 +1,157 @@
+use std::error::Error;
+
+use serde_json::{json, Value};
+
+use crate::requirements::network::udev::{self, NamingRule};
+use crate::requirements::network::{self, InterfaceConfig};
+use crate::requirements::{CheckResult, FixContext, Requirement, Severity, SystemRoot};
+
+struct MacBindingsCheckResult {
+    /// The interface configurations bound to a MAC address.
+    configs: Vec<InterfaceConfig>,
+    udev_rules: Vec<NamingRule>,
+    /// The MAC addresses of the running system, only known on the host.
+    host_mac_addresses: Option<Vec<String>>,
+}
+
+impl MacBindingsCheckResult {
+    fn is_present(&self, mac_address: &str) -> Option<bool> {
+        self.host_mac_addresses.as_ref().map(|addresses| addresses.iter().any(|address| address == mac_address))
+    }
+
+    fn bindings(&self) -> Vec<String> {
+        let configs = self.configs.iter().map(|config| {
+            format!("{} bound to {} in {}", config.id, config.mac_address.as_deref().unwrap_or_default(), config.path)
+        });
+        let rules = self.udev_rules.iter().map(|rule| match &rule.name {
+            Some(name) => format!("{} named after {} in {}", name, rule.mac_address, udev::PERSISTENT_NET_RULES),
+            None => format!("rule for {} in {}", rule.mac_address, udev::PERSISTENT_NET_RULES),
+        });
+        configs.chain(rules).collect()
+    }
+}
+
+impl CheckResult for MacBindingsCheckResult {
+    fn passed(&self) -> bool {
+        self.configs.is_empty() && self.udev_rules.is_empty()
+    }
+    fn log(&self) {
+        if self.passed() {
+            log::info!("{}", self.message());
+        } else {
+            log::warn!("The network configuration is bound to MAC addresses");
+            for binding in self.bindings() {
+                log::warn!("{}", binding);
+            }
+        }
+    }
+    fn message(&self) -> String {
+        if self.passed() {
+            "The network configuration is not bound to MAC addresses".to_string()
+        } else {
+            format!("The network configuration is bound to MAC addresses: {}", self.bindings().join(", "))
+        }
+    }
+    fn details(&self) -> Value {
+        let configs: Vec<Value> = self
+            .configs
+            .iter()
+            .map(|config| {
+                json!({
+                    "backend": config.backend,
+                    "path": config.path,
+                    "id": config.id,
+                    "mac_address": config.mac_address,
+                    "present": config.mac_address.as_deref().and_then(|mac| self.is_present(mac)),
+                })
+            })
+            .collect();
+        let udev_rules: Vec<Value> = self
+            .udev_rules
+            .iter()
+            .map(|rule| json!({ "name": rule.name, "mac_address": rule.mac_address, "present": self.is_present(&rule.mac_address) }))
+            .collect();
+
+        json!({ "configs": configs, "udev_rules": udev_rules })
+    }
+    fn severity(&self) -> Severity {
+        if self.passed() {
+            Severity::Info
+        } else {
+            Severity::Blocker
+        }
+    }
+    fn remediation(&self) -> Option<String> {
+        if self.passed() {
+            None
+        } else {
+            Some(format!("The instance gets new NICs with other MAC addresses. Remove the MAC address matches from the network configuration and the rules of {}, or apply the fix of this requirement.", udev::PERSISTENT_NET_RULES))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MacBindingsRequirement;
+
+impl Requirement for MacBindingsRequirement {
+    fn name(&self) -> &'static str {
+        "mac-bindings"
+    }
+    fn check(&self, root: &SystemRoot) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
+        let result = check_mac_bindings(root)?;
+        result.log();
+        Ok(Box::new(result))
+    }
+    fn fix(&self, ctx: &mut FixContext) -> Option<Result<bool, Box<dyn Error>>> {
+        Some(fix_mac_bindings(ctx))
+    }
+    fn fixable(&self) -> bool {
+        true
+    }
+}
+
+/// Finds the interface configurations bound to a MAC address, e.g. a netplan `match: macaddress`
+/// or an `HWADDR=` in an `ifcfg-*` file, and the legacy persistent-net udev rules.
+fn check_mac_bindings(root: &SystemRoot) -> Result<MacBindingsCheckResult, Box<dyn Error>> {
+    let configs = network::inspect(root)?.into_iter().filter(|config| config.mac_address.is_some()).collect();
+    let udev_rules = udev::load(root)?;
+    let host_mac_addresses = if root.is_host() { Some(network::host_mac_addresses()) } else { None };
+
+    Ok(MacBindingsCheckResult { configs, udev_rules, host_mac_addresses })
+}
+
+/// Strips the MAC addresses from the configurations, in the format of their backend, and removes the naming rules.
+fn fix_mac_bindings(ctx: &mut FixContext) -> Result<bool, Box<dyn Error>> {
+    let result = check_mac_bindings(ctx.root())?;
+
+    for config in &result.configs {
+        log::info!("Removing the MAC address binding of {} from {}", config.id, config.path);
+        network::remove_mac_binding(ctx, config)?;
+    }
+    if !result.udev_rules.is_empty() {
+        log::info!("Removing the naming rules of {}", udev::PERSISTENT_NET_RULES);
+        udev::remove_naming_rules(ctx)?;
+    }
+    Ok(true)
+}
+
+#[test]
+fn test_bindings_are_found_and_removed() {
+    let root = crate::tests::fixture_root("mac-bindings", &[
+        ("/etc/sysconfig/network-scripts/ifcfg-eth0", "DEVICE=eth0\nBOOTPROTO=dhcp\nHWADDR=52:54:00:AB:CD:EF\n"),
+        ("/etc/sysconfig/network-scripts/ifcfg-eth1", "DEVICE=eth1\nBOOTPROTO=dhcp\n"),
+        (udev::PERSISTENT_NET_RULES, "# generated\nSUBSYSTEM==\"net\", ACTION==\"add\", ATTR{address}==\"52:54:00:ab:cd:ef\", NAME=\"eth0\"\n"),
+    ]);
+
+    let result = check_mac_bindings(&root).unwrap();
+
+    assert!(!result.passed());
+    assert_eq!(result.configs.len(), 1);
+    assert_eq!(result.details()["udev_rules"][0]["name"], "eth0");
+
+    fix_mac_bindings(&mut FixContext::new(&root, false)).unwrap();
+
+    assert!(check_mac_bindings(&root).unwrap().passed());
+    assert_eq!(root.read_to_string("/etc/sysconfig/network-scripts/ifcfg-eth0").unwrap(), "DEVICE=eth0\nBOOTPROTO=dhcp\n");
+    assert_eq!(root.read_to_string(udev::PERSISTENT_NET_RULES).unwrap(), "# generated\n");
+}
@@ -6,4 +6,5 @@ pub mod kernel_args;
 pub mod dhcp_enabled;
 pub mod cloud_init;
 pub mod boot_disk_size;
-pub mod virtio_drivers;
\ No newline at end of file
+pub mod virtio_drivers;
+pub mod mac_bindings;
\ No newline at end of file
@@ -1,78 +1,197 @@
 use std::any::Any;
 use std::error::Error;
-use std::ops::Deref;
 
-use sysinfo::{System, SystemExt};
+use chrono::{NaiveDate, Utc};
+use serde_json::{json, Value};
 
-use crate::requirements::{CheckResult, Requirement};
+use crate::requirements::os_matrix::{OsMatrix, OsMatrixEntry};
+use crate::requirements::os_release::{OsRelease, OS_RELEASE_PATHS};
+use crate::requirements::{CheckResult, Requirement, Severity, SystemRoot};
 
 #[derive(Debug)]
-pub struct OSSupportRequirement;
+pub struct OSSupportRequirement {
+    pub matrix: OsMatrix,
+}
 
 impl Requirement for OSSupportRequirement {
-    fn check(&self) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
-        let result = check_os_support()?;
+    fn name(&self) -> &'static str {
+        "os-support"
+    }
+    fn check(&self, root: &SystemRoot) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
+        let result = check_os_support(root, &self.matrix, Utc::now().date_naive())?;
         result.log();
         Ok(Box::new(result))
     }
 }
 
 struct OsCheckResult {
-    pub os_name: String,
-    pub os_version: String,
-    pub is_supported: bool,
+    /// None when the system has no `os-release` to identify it.
+    pub os_release: Option<OsRelease>,
+    /// The supported system the OS matched, its `ID` or one of its `ID_LIKE`.
+    pub matched_id: Option<String>,
+    /// The entry of the OS matrix for the release, None when it is not supported.
+    pub entry: Option<OsMatrixEntry>,
+    pub is_end_of_support: bool,
 }
 
+impl OsCheckResult {
+    fn os_name(&self) -> &str {
+        self.os_release.as_ref().map_or("unknown", |os| os.id.as_str())
+    }
+
+    fn os_version(&self) -> &str {
+        self.os_release.as_ref().and_then(|os| os.version_id.as_deref()).unwrap_or("unknown version")
+    }
+}
 
 impl CheckResult for OsCheckResult {
     fn passed(&self) -> bool {
-        self.is_supported
+        self.entry.is_some() && !self.is_end_of_support
     }
     fn log(&self) {
-        let result = self.deref();
-        if result.is_supported {
-            log::info!("{}, {} is supported", result.os_name, result.os_version);
+        if self.passed() {
+            log::info!("{}", self.message());
+        } else if self.entry.is_some() {
+            log::warn!("{}", self.message());
         } else {
-            log::error!("{}, {} is not supported", result.os_name, result.os_version);
+            log::error!("{}", self.message());
+        }
+        if let Some(notes) = self.entry.as_ref().and_then(|entry| entry.notes.as_ref()) {
+            log::info!("{}", notes);
+        }
+    }
+    fn message(&self) -> String {
+        let (os_name, os_version) = (self.os_name(), self.os_version());
+        match (&self.os_release, &self.entry) {
+            (None, _) => format!("The operating system could not be identified, found none of {}", OS_RELEASE_PATHS.join(", ")),
+            (Some(_), Some(entry)) => {
+                let mut notes = vec![entry.name.clone()];
+                if entry.byol {
+                    notes.push("BYOL".to_string());
+                }
+                match entry.end_of_support {
+                    Some(end_of_support) if self.is_end_of_support => {
+                        format!("{}, {} reached its end of support on {} ({})", os_name, os_version, end_of_support, notes.join(", "))
+                    }
+                    Some(end_of_support) => {
+                        notes.push(format!("supported until {}", end_of_support));
+                        format!("{}, {} is supported ({})", os_name, os_version, notes.join(", "))
+                    }
+                    None => format!("{}, {} is supported ({})", os_name, os_version, notes.join(", ")),
+                }
+            }
+            (Some(_), None) => match self.matched_id.as_deref() {
+                Some(matched_id) if matched_id != os_name => format!("{}, {} is not supported as {}", os_name, os_version, matched_id),
+                _ => format!("{}, {} is not supported", os_name, os_version),
+            },
         }
     }
+    fn details(&self) -> Value {
+        json!({
+            "os_name": self.os_release.as_ref().map(|os| &os.id),
+            "os_version": self.os_release.as_ref().and_then(|os| os.version_id.as_ref()),
+            "id_like": self.os_release.as_ref().map(|os| &os.id_like),
+            "pretty_name": self.os_release.as_ref().and_then(|os| os.pretty_name.as_ref()),
+            "matched_id": self.matched_id,
+            "entry": self.entry,
+            "end_of_support_reached": self.is_end_of_support,
+        })
+    }
+    fn severity(&self) -> Severity {
+        if self.passed() {
+            Severity::Info
+        } else if self.entry.is_some() {
+            // the image still works, but no longer gets updates
+            Severity::Warning
+        } else {
+            Severity::Blocker
+        }
+    }
+    fn remediation(&self) -> Option<String> {
+        if self.passed() {
+            None
+        } else if self.entry.is_some() {
+            Some("Upgrade to a release of the operating system that is still supported, or check the extended support offered by its vendor.".to_string())
+        } else {
+            Some("Use one of the operating system versions supported by IBM Cloud VPC custom images.".to_string())
+        }
+    }
+    fn documentation_url(&self) -> &'static str {
+        "https://cloud.ibm.com/docs/vpc?topic=vpc-about-images"
+    }
 }
 
 
-fn check_os_support() -> Result<OsCheckResult, Box<dyn Error>> {
-    // Supported OS list
-    let supported_os = [
-        ("debian", vec!["10", "11"]),
-        ("rhel", vec!["7", "8", "9"]),
-        ("rocky", vec!["8.5"]),
-        ("suse", vec!["12", "15"]),
-        ("ubuntu", vec!["18.04", "20.04", "22.04"]),
-        ("windows", vec!["2012", "2012 R2", "2016", "2019", "2022"]),
-        ("fedora", vec![]),  // Fedora Core OS has no version
-    ];
-
-    let mut sys = System::new();
-    sys.refresh_system();
-
-    let os_name = sys.name().expect("Failed to get OS Name").to_lowercase();
-    let os_version = sys.os_version().expect("Failed to get OS Version").to_lowercase();
-
-    // instead of printing the about compatibility, it would be better to return os name, os version, and a boolean
-    for (name, versions) in &supported_os {
-        if os_name == *name && versions.contains(&os_version.as_str()) {
-            return Ok(OsCheckResult {
-                os_name,
-                os_version,
-                is_supported: true,
-            });
-        }
+/// Identifies the OS from its `os-release` and looks up its release in the OS matrix.
+fn check_os_support(root: &SystemRoot, matrix: &OsMatrix, today: NaiveDate) -> Result<OsCheckResult, Box<dyn Error>> {
+    let Some(os_release) = OsRelease::load(root)? else {
+        return Ok(OsCheckResult { os_release: None, matched_id: None, entry: None, is_end_of_support: false });
+    };
+
+    let matched_id = matrix.family(&os_release).map(str::to_string);
+    let entry = matched_id.as_deref().and_then(|family| matrix.entry(family, &os_release)).cloned();
+    let is_end_of_support = entry.as_ref().is_some_and(|entry| entry.is_end_of_support(today));
+
+    Ok(OsCheckResult { os_release: Some(os_release), matched_id, entry, is_end_of_support })
+}
+
+#[cfg(test)]
+fn check_with_matrix(name: &str, os_release: &str) -> OsCheckResult {
+    let root = crate::tests::fixture_root(name, &[("/usr/lib/os-release", os_release)]);
+    check_os_support(&root, &OsMatrix::embedded().unwrap(), NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()).unwrap()
+}
+
+#[test]
+fn test_os_support_in_root() {
+    let result = check_with_matrix("os-support", "NAME=\"Ubuntu\"\nVERSION_ID=\"22.04\"\nID=ubuntu\n");
+
+    assert_eq!(result.os_name(), "ubuntu");
+    assert!(result.passed());
+    assert_eq!(result.details()["entry"]["name"], "Ubuntu 22.04 LTS");
+}
+
+#[test]
+fn test_os_support_matches_version_ranges_and_variants() {
+    assert!(check_with_matrix("os-ubuntu-point", "ID=ubuntu\nVERSION_ID=\"22.04.3\"\n").passed());
+    assert!(check_with_matrix("os-rhel-minor", "ID=\"rhel\"\nID_LIKE=\"fedora\"\nVERSION_ID=\"8.8\"\n").passed());
+    assert!(check_with_matrix("os-ubuntu-interim", "ID=ubuntu\nVERSION_ID=\"23.10\"\n").entry.is_none());
+
+    let sles = check_with_matrix("os-sles", "ID=\"sles\"\nID_LIKE=\"suse\"\nVERSION_ID=\"15.5\"\n");
+    assert!(sles.passed());
+    assert_eq!(sles.message(), "sles, 15.5 is supported (SUSE Linux Enterprise Server 15, supported until 2031-07-31)");
+    assert!(check_with_matrix("os-coreos", "ID=fedora\nVARIANT_ID=coreos\nVERSION_ID=39\n").passed());
+}
+
+#[test]
+fn test_os_support_rejects_relatives_of_supported_systems() {
+    for (name, os_release) in [
+        ("os-fedora", "ID=fedora\nVERSION_ID=39\n"),
+        ("os-alma", "ID=\"almalinux\"\nID_LIKE=\"rhel centos fedora\"\nVERSION_ID=\"9.2\"\n"),
+        ("os-centos", "ID=\"centos\"\nID_LIKE=\"rhel fedora\"\nVERSION_ID=\"9\"\n"),
+        ("os-oracle", "ID=\"ol\"\nID_LIKE=\"fedora\"\nVERSION_ID=\"9.2\"\n"),
+        ("os-opensuse", "ID=\"opensuse-leap\"\nID_LIKE=\"suse opensuse\"\nVERSION_ID=\"15.5\"\n"),
+    ] {
+        let result = check_with_matrix(name, os_release);
+        assert_eq!(result.severity(), Severity::Blocker, "{}", name);
+        assert_eq!(result.entry, None, "{}", name);
+        assert_eq!(result.matched_id, None, "{}", name);
     }
+}
 
-    Ok(OsCheckResult {
-        os_name,
-        os_version,
-        is_supported: false,
-    })
+#[test]
+fn test_os_support_reports_the_end_of_support() {
+    let result = check_with_matrix("os-ubuntu-eol", "ID=ubuntu\nVERSION_ID=\"18.04\"\n");
+
+    assert!(!result.passed());
+    assert_eq!(result.severity(), Severity::Warning);
+    assert_eq!(result.message(), "ubuntu, 18.04 reached its end of support on 2023-05-31 (Ubuntu 18.04 LTS)");
 }
 
+#[test]
+fn test_os_support_without_os_release() {
+    let root = crate::tests::fixture_root("os-unknown", &[]);
+    let result = check_os_support(&root, &OsMatrix::embedded().unwrap(), NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()).unwrap();
 
+    assert!(!result.passed());
+    assert_eq!(result.details()["os_name"], Value::Null);
+}
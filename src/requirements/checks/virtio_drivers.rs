@@ -2,27 +2,106 @@ use std::any::Any;
 use std::error::Error;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::ops::Deref;
+use std::path::Path;
 use std::process::Command;
 
+use serde_json::{json, Value};
 use thiserror::Error;
 
-use crate::requirements::{CheckResult, Requirement};
-use crate::utils;
+use crate::requirements::{CheckResult, FixContext, Requirement, Severity, SystemRoot};
+use crate::requirements::kernel::{initramfs, initramfs_generator};
+use crate::requirements::kernel::kconfig::{self, KernelConfig};
+use crate::requirements::kernel::modules::ModulesIndex;
+use crate::requirements::kernel::DriverState;
+use crate::utils::{self, FileError};
 
 #[derive(Debug)]
 pub struct VitioDriversRequirement;
 
 impl Requirement for VitioDriversRequirement {
-    fn check(&self) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
-        let result = check_virtio_drivers()?;
+    fn name(&self) -> &'static str {
+        "virtio-drivers"
+    }
+    fn check(&self, root: &SystemRoot) -> Result<Box<dyn CheckResult>, Box<dyn Error>> {
+        let result = check_virtio_drivers(root)?;
         result.log();
         Ok(Box::new(result))
     }
+    fn fix(&self, ctx: &mut FixContext) -> Option<Result<bool, Box<dyn Error>>> {
+        Some(fix_virtio_drivers(ctx))
+    }
+    fn fixable(&self) -> bool {
+        true
+    }
 }
 
+/// A VIRTIO driver, with its module name and its Kconfig option.
+struct VirtioDriver {
+    module: &'static str,
+    option: &'static str,
+    /// Drivers the instance cannot boot or reach the network without. The others are reported only.
+    required: bool,
+}
+
+const VIRTIO_DRIVERS: [VirtioDriver; 5] = [
+    VirtioDriver { module: "virtio_pci", option: "CONFIG_VIRTIO_PCI", required: true },
+    VirtioDriver { module: "virtio_blk", option: "CONFIG_VIRTIO_BLK", required: true },
+    VirtioDriver { module: "virtio_net", option: "CONFIG_VIRTIO_NET", required: true },
+    VirtioDriver { module: "virtio_scsi", option: "CONFIG_SCSI_VIRTIO", required: false },
+    VirtioDriver { module: "virtio_console", option: "CONFIG_VIRTIO_CONSOLE", required: false },
+];
+
+type DriverStates = Vec<(&'static VirtioDriver, DriverState)>;
+
 struct VirtioDriversCheckResult {
     passed: bool,
+    kernel_version: String,
+    /// Where the driver states were read from, e.g. `/boot/config-5.15.0-91-generic`.
+    source: String,
+    drivers: DriverStates,
+    /// The initramfs of every installed kernel, the checked one included.
+    initramfs: Vec<KernelInitramfs>,
+}
+
+/// The required drivers a kernel builds as modules, and whether its initramfs contains them.
+struct KernelInitramfs {
+    release: String,
+    path: Option<String>,
+    missing_modules: Vec<&'static str>,
+    /// Why the initramfs could not be inspected, e.g. an unsupported compression.
+    error: Option<String>,
+}
+
+impl KernelInitramfs {
+    fn is_complete(&self) -> bool {
+        self.missing_modules.is_empty() && self.error.is_none()
+    }
+
+    fn problem(&self) -> String {
+        match (&self.path, &self.error) {
+            (_, Some(error)) => format!("{}: {}", self.release, error),
+            (None, None) => format!("{}: no initramfs for the modules {}", self.release, self.missing_modules.join(", ")),
+            (Some(path), None) => format!("{}: {} lacks {}", self.release, path, self.missing_modules.join(", ")),
+        }
+    }
+}
+
+impl VirtioDriversCheckResult {
+    fn missing(&self, required: bool) -> Vec<&'static str> {
+        self.drivers
+            .iter()
+            .filter(|(driver, state)| driver.required == required && *state == DriverState::Missing)
+            .map(|(driver, _)| driver.module)
+            .collect()
+    }
+
+    fn incomplete_initramfs(&self) -> impl Iterator<Item = &KernelInitramfs> {
+        self.initramfs.iter().filter(|initramfs| !initramfs.is_complete())
+    }
+
+    fn kernel_initramfs(&self) -> Option<&KernelInitramfs> {
+        self.initramfs.iter().find(|initramfs| initramfs.release == self.kernel_version)
+    }
 }
 
 impl CheckResult for VirtioDriversCheckResult {
@@ -30,26 +109,196 @@ impl CheckResult for VirtioDriversCheckResult {
         self.passed
     }
     fn log(&self) {
-        let result = self.deref();
-        if result.passed {
-            log::info!("VIRTIO drivers are present");
+        if self.passed {
+            log::info!("{}", self.message());
+            // the message of a failed check already lists them
+            for initramfs in self.incomplete_initramfs() {
+                log::warn!("VIRTIO modules are missing from the initramfs of {}", initramfs.problem());
+            }
         } else {
-            log::warn!("VIRTIO drivers are not present");
+            log::warn!("{}", self.message());
+        }
+        let optional = self.missing(false);
+        if !optional.is_empty() {
+            log::info!("Optional VIRTIO drivers not present: {}", optional.join(", "));
         }
     }
+    fn message(&self) -> String {
+        if self.passed {
+            return "VIRTIO drivers are present".to_string();
+        }
+
+        let mut problems = vec![];
+        let missing = self.missing(true);
+        if !missing.is_empty() {
+            problems.push(format!("VIRTIO drivers are not present: {}", missing.join(", ")));
+        }
+        let incomplete: Vec<String> = self.incomplete_initramfs().map(KernelInitramfs::problem).collect();
+        if !incomplete.is_empty() {
+            problems.push(format!("VIRTIO modules are missing from the initramfs of {}", incomplete.join("; ")));
+        }
+        problems.join(". ")
+    }
+    fn details(&self) -> Value {
+        let drivers: serde_json::Map<String, Value> = self
+            .drivers
+            .iter()
+            .map(|(driver, state)| (driver.module.to_string(), json!(state)))
+            .collect();
+
+        let initramfs: Vec<Value> = self
+            .initramfs
+            .iter()
+            .map(|initramfs| {
+                json!({
+                    "release": initramfs.release,
+                    "path": initramfs.path,
+                    "missing_modules": initramfs.missing_modules,
+                    "error": initramfs.error,
+                })
+            })
+            .collect();
+
+        json!({ "kernel_version": self.kernel_version, "source": self.source, "drivers": drivers, "initramfs": initramfs })
+    }
+    fn severity(&self) -> Severity {
+        if self.passed {
+            Severity::Info
+        } else {
+            Severity::Blocker
+        }
+    }
+    fn remediation(&self) -> Option<String> {
+        if self.passed {
+            return None;
+        }
+        let missing = self.missing(true);
+        if !missing.is_empty() {
+            return Some(format!("Install a kernel that provides the {} drivers, built in or as modules.", missing.join(", ")));
+        }
+        Some("Add the VIRTIO modules to the initramfs, e.g. with `add_drivers+=\" virtio_blk virtio_net virtio_pci \"` in /etc/dracut.conf.d or in /etc/initramfs-tools/modules, then regenerate it, or apply the fix of this requirement.".to_string())
+    }
 }
 
-/// Checks if the VIRTIO_BLK and VIRTIO_NET drivers are present in the kernel.
-/// It reads the kernel configuration file located in `/boot/config-<kernel_version>`
-/// and checks if the strings `VIRTIO_BLK` and `VIRTIO_NET` are present.
-fn check_virtio_drivers() -> Result<VirtioDriversCheckResult, Box<dyn Error>> {
-    let kernel_version = get_kernel_version();
-    let config_file = format!("/boot/config-{}", kernel_version?);
-    let config_content = utils::read_file_to_string(&config_file);
+/// Checks how the kernel provides the VIRTIO drivers, and that the initramfs of every installed
+/// kernel contains the required drivers built as modules. Only the checked kernel must pass,
+/// the other kernels are reported.
+fn check_virtio_drivers(root: &SystemRoot) -> Result<VirtioDriversCheckResult, Box<dyn Error>> {
+    let kernel_version = get_kernel_version(root)?;
+    let (source, drivers) = kernel_drivers(root, &kernel_version)?;
+
+    let mut kernels = installed_kernels(root).unwrap_or_default();
+    if !kernels.contains(&kernel_version) {
+        kernels.push(kernel_version.clone());
+    }
 
-    let passed = config_content.contains("VIRTIO_BLK") && config_content.contains("VIRTIO_NET");
+    let mut initramfs = vec![];
+    for release in kernels {
+        let required_modules = if release == kernel_version {
+            Ok(required_modules(&drivers))
+        } else {
+            kernel_drivers(root, &release).map(|(_, drivers)| required_modules(&drivers))
+        };
+        initramfs.push(match required_modules {
+            Ok(modules) => check_initramfs(root, &release, &modules),
+            Err(e) => KernelInitramfs {
+                path: initramfs::find(root, &release),
+                release,
+                missing_modules: vec![],
+                error: Some(e.to_string()),
+            },
+        });
+    }
 
-    Ok(VirtioDriversCheckResult { passed })
+    let mut result = VirtioDriversCheckResult { passed: false, kernel_version, source, drivers, initramfs };
+    result.passed = result.missing(true).is_empty() && result.kernel_initramfs().is_none_or(KernelInitramfs::is_complete);
+    Ok(result)
+}
+
+/// Reads the driver states of a kernel from `/boot/config-<release>`, or from `/proc/config.gz`
+/// for the running kernel. Without either, the `modules.builtin` and `modules.dep` indexes of
+/// `/lib/modules/<release>` are used.
+fn kernel_drivers(root: &SystemRoot, release: &str) -> Result<(String, DriverStates), Box<dyn Error>> {
+    let config_file = format!("/boot/config-{}", release);
+
+    Ok(if root.path(&config_file).is_file() {
+        let config = KernelConfig::parse(&root.read_to_string(&config_file)?);
+        (config_file, driver_states(|driver| config.driver_state(driver.option)))
+    } else if root.is_host() && is_running(release) && Path::new(kconfig::PROC_CONFIG).exists() {
+        let config = KernelConfig::from_proc()?;
+        (kconfig::PROC_CONFIG.to_string(), driver_states(|driver| config.driver_state(driver.option)))
+    } else {
+        let index = ModulesIndex::load(root, release)?;
+        let source = format!("/lib/modules/{}/modules.builtin", release);
+        (source, driver_states(|driver| index.driver_state(driver.module)))
+    })
+}
+
+fn is_running(release: &str) -> bool {
+    get_kernel_version(&SystemRoot::host()).map(|running| running == release).unwrap_or(false)
+}
+
+/// The required drivers that are built as modules, so the initramfs must contain them to mount the root disk.
+fn required_modules(drivers: &[(&'static VirtioDriver, DriverState)]) -> Vec<&'static str> {
+    drivers
+        .iter()
+        .filter(|(driver, state)| driver.required && *state == DriverState::Module)
+        .map(|(driver, _)| driver.module)
+        .collect()
+}
+
+fn check_initramfs(root: &SystemRoot, release: &str, required_modules: &[&'static str]) -> KernelInitramfs {
+    let path = initramfs::find(root, release);
+    let mut result = KernelInitramfs { release: release.to_string(), path: path.clone(), missing_modules: vec![], error: None };
+    if required_modules.is_empty() {
+        return result;
+    }
+
+    let modules = match path {
+        Some(path) => std::fs::read(root.path(&path))
+            .map_err(|e| FileError::new("read", &path, e).into())
+            .and_then(|data| initramfs::modules(&data).map_err(Box::<dyn Error>::from)),
+        None => Ok(vec![]),
+    };
+    match modules {
+        Ok(modules) => {
+            result.missing_modules = required_modules
+                .iter()
+                .filter(|&&module| !modules.iter().any(|name| name == module))
+                .cloned()
+                .collect();
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result
+}
+
+/// Adds the required drivers to the initramfs configuration of the distribution, then regenerates
+/// the initramfs of every installed kernel. The previous images are backed up, so a failure
+/// restores them. A kernel without the drivers cannot be fixed this way.
+fn fix_virtio_drivers(ctx: &mut FixContext) -> Result<bool, Box<dyn Error>> {
+    let result = check_virtio_drivers(ctx.root())?;
+    let missing = result.missing(true);
+    if !missing.is_empty() {
+        return Err(format!("Kernel {} does not provide the {} drivers, install a kernel that does", result.kernel_version, missing.join(", ")).into());
+    }
+
+    let generator = initramfs_generator::detect(ctx.root())?;
+    let modules: Vec<&str> = VIRTIO_DRIVERS.iter().filter(|driver| driver.required).map(|driver| driver.module).collect();
+
+    log::info!("Adding {} to the initramfs configuration of {}", modules.join(", "), generator.name());
+    generator.add_modules(ctx, &modules)?;
+
+    // the complete images are left alone, so they are not backed up for nothing
+    for initramfs in result.incomplete_initramfs() {
+        log::info!("Regenerating the initramfs of {}", initramfs.release);
+        generator.regenerate(ctx, &initramfs.release)?;
+    }
+    Ok(true)
+}
+
+fn driver_states(state: impl Fn(&VirtioDriver) -> DriverState) -> DriverStates {
+    VIRTIO_DRIVERS.iter().map(|driver| (driver, state(driver))).collect()
 }
 
 #[derive(Error, Debug)]
@@ -58,9 +307,21 @@ enum KernelVersionError {
     CommandExecutionFailed(#[from] std::io::Error),
     #[error("Command returned non-zero status: {0}")]
     NonZeroExitStatus(i32),
+    #[error("Failed to list the installed kernels in {0}")]
+    ListInstalledKernels(String, #[source] std::io::Error),
+    #[error("No kernel found in {0}")]
+    NoInstalledKernel(String),
 }
 
-fn get_kernel_version() -> Result<String, KernelVersionError> {
+/// Returns the release of the running kernel. On an alternate root there is
+/// no running kernel, so the newest kernel installed in `/lib/modules` is used.
+fn get_kernel_version(root: &SystemRoot) -> Result<String, KernelVersionError> {
+    if !root.is_host() {
+        return installed_kernels(root)?
+            .pop()
+            .ok_or_else(|| KernelVersionError::NoInstalledKernel(root.path("/lib/modules").display().to_string()));
+    }
+
     let output = Command::new("uname")
         .arg("-r")
         .output()?;
@@ -71,4 +332,126 @@ fn get_kernel_version() -> Result<String, KernelVersionError> {
 
     let kernel_version = String::from_utf8_lossy(&output.stdout);
     Ok(kernel_version.trim().to_string())
-}
\ No newline at end of file
+}
+
+/// Lists the kernel releases installed in `/lib/modules`, oldest first.
+fn installed_kernels(root: &SystemRoot) -> Result<Vec<String>, KernelVersionError> {
+    let modules_dir = root.path("/lib/modules");
+    let mut kernels: Vec<String> = std::fs::read_dir(&modules_dir)
+        .map_err(|e| KernelVersionError::ListInstalledKernels(modules_dir.display().to_string(), e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+
+    kernels.sort_by(|a, b| utils::compare_versions(a, b));
+    Ok(kernels)
+}
+
+#[test]
+fn test_kernel_version_in_root_is_newest_installed() {
+    let root = crate::tests::fixture_root("kernel-version", &[
+        ("/lib/modules/5.15.0-9-generic/modules.dep", ""),
+        ("/lib/modules/5.15.0-91-generic/modules.dep", ""),
+        ("/lib/modules/5.4.0-150-generic/modules.dep", ""),
+    ]);
+
+    assert_eq!(get_kernel_version(&root).unwrap(), "5.15.0-91-generic");
+}
+
+#[test]
+fn test_commented_out_options_are_missing() {
+    let root = crate::tests::fixture_root("virtio-kconfig", &[
+        ("/lib/modules/5.15.0-91-generic/modules.dep", ""),
+        ("/boot/config-5.15.0-91-generic", "CONFIG_VIRTIO_PCI=y\n# CONFIG_VIRTIO_BLK is not set\nCONFIG_VIRTIO_NET=m\n"),
+    ]);
+
+    let result = check_virtio_drivers(&root).unwrap();
+
+    assert!(!result.passed);
+    assert_eq!(result.missing(true), vec!["virtio_blk"]);
+    assert_eq!(result.missing(false), vec!["virtio_scsi", "virtio_console"]);
+}
+
+#[test]
+fn test_modules_index_without_kernel_config() {
+    let initramfs = initramfs::cpio(&[
+        "usr/lib/modules/6.1.0-13-amd64/kernel/drivers/block/virtio_blk.ko",
+        "usr/lib/modules/6.1.0-13-amd64/kernel/drivers/net/virtio_net.ko",
+    ]);
+    let root = crate::tests::fixture_root("virtio-modules", &[
+        ("/lib/modules/6.1.0-13-amd64/modules.builtin", "kernel/drivers/virtio/virtio_pci.ko\n"),
+        ("/lib/modules/6.1.0-13-amd64/modules.dep", "kernel/drivers/block/virtio_blk.ko:\nkernel/drivers/net/virtio_net.ko:\n"),
+        ("/boot/initrd.img-6.1.0-13-amd64", std::str::from_utf8(&initramfs).unwrap()),
+    ]);
+
+    let result = check_virtio_drivers(&root).unwrap();
+
+    assert!(result.passed);
+    assert_eq!(result.source, "/lib/modules/6.1.0-13-amd64/modules.builtin");
+    assert_eq!(result.details()["drivers"]["virtio_blk"], "module");
+}
+
+#[test]
+fn test_initramfs_of_every_kernel_is_checked() {
+    let modules = "kernel/drivers/block/virtio_blk.ko:\nkernel/drivers/net/virtio_net.ko:\n";
+    let complete = initramfs::cpio(&[
+        "usr/lib/modules/6.1.0-13-amd64/kernel/drivers/block/virtio_blk.ko",
+        "usr/lib/modules/6.1.0-13-amd64/kernel/drivers/net/virtio_net.ko",
+    ]);
+    let incomplete = initramfs::cpio(&["usr/lib/modules/5.10.0-26-amd64/kernel/drivers/block/virtio_blk.ko"]);
+    let root = crate::tests::fixture_root("virtio-initramfs", &[
+        ("/lib/modules/5.10.0-26-amd64/modules.builtin", "kernel/drivers/virtio/virtio_pci.ko\n"),
+        ("/lib/modules/5.10.0-26-amd64/modules.dep", modules),
+        ("/lib/modules/6.1.0-13-amd64/modules.builtin", "kernel/drivers/virtio/virtio_pci.ko\n"),
+        ("/lib/modules/6.1.0-13-amd64/modules.dep", modules),
+        ("/boot/initrd.img-5.10.0-26-amd64", std::str::from_utf8(&incomplete).unwrap()),
+        ("/boot/initrd.img-6.1.0-13-amd64", std::str::from_utf8(&complete).unwrap()),
+    ]);
+
+    let result = check_virtio_drivers(&root).unwrap();
+
+    assert!(result.passed);
+    let incomplete: Vec<&KernelInitramfs> = result.incomplete_initramfs().collect();
+    assert_eq!(incomplete.len(), 1);
+    assert_eq!(incomplete[0].release, "5.10.0-26-amd64");
+    assert_eq!(incomplete[0].missing_modules, vec!["virtio_net"]);
+
+    std::fs::remove_file(root.path("/boot/initrd.img-6.1.0-13-amd64")).unwrap();
+    let result = check_virtio_drivers(&root).unwrap();
+
+    assert!(!result.passed);
+    assert_eq!(result.kernel_initramfs().unwrap().missing_modules, vec!["virtio_blk", "virtio_net"]);
+}
+
+#[test]
+fn test_fix_regenerates_only_incomplete_initramfs() {
+    use crate::requirements::fix::Change;
+
+    let modules = "kernel/drivers/block/virtio_blk.ko:\nkernel/drivers/net/virtio_net.ko:\n";
+    let complete = initramfs::cpio(&[
+        "usr/lib/modules/6.1.0-13-amd64/kernel/drivers/block/virtio_blk.ko",
+        "usr/lib/modules/6.1.0-13-amd64/kernel/drivers/net/virtio_net.ko",
+    ]);
+    let root = crate::tests::fixture_root("virtio-initramfs-fix", &[
+        ("/lib/modules/5.10.0-26-amd64/modules.builtin", "kernel/drivers/virtio/virtio_pci.ko\n"),
+        ("/lib/modules/5.10.0-26-amd64/modules.dep", modules),
+        ("/lib/modules/6.1.0-13-amd64/modules.builtin", "kernel/drivers/virtio/virtio_pci.ko\n"),
+        ("/lib/modules/6.1.0-13-amd64/modules.dep", modules),
+        ("/etc/initramfs-tools/modules", "# List of modules\n"),
+        ("/usr/sbin/update-initramfs", ""),
+        ("/boot/initrd.img-6.1.0-13-amd64", std::str::from_utf8(&complete).unwrap()),
+    ]);
+    let mut ctx = FixContext::new(&root, true);
+
+    fix_virtio_drivers(&mut ctx).unwrap();
+
+    match ctx.changes() {
+        [Change::WriteFile { path, diff }, Change::RunCommand { command }] => {
+            assert_eq!(path, initramfs_generator::INITRAMFS_TOOLS_MODULES);
+            assert!(diff.contains("+virtio_pci\n+virtio_blk\n+virtio_net\n"));
+            assert_eq!(command, "/usr/sbin/update-initramfs -c -k 5.10.0-26-amd64");
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+}
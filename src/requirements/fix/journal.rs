@@ -0,0 +1,236 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::requirements::fix::{Change, Transaction};
+use crate::requirements::SystemRoot;
+use crate::utils::FileError;
+
+/// Where the tool keeps its state, inside the target system so it survives reboots
+/// and travels with the disk when an alternate root is prepared.
+pub const STATE_DIR: &str = "/var/lib/vpc-migration-tools";
+
+const ENTRY_FILE: &str = "entry.json";
+
+/// An applied fix, with the backup copies needed to restore the state before it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub requirement: String,
+    /// The files changed by the fix, in the order they were first modified.
+    pub files: Vec<JournaledFile>,
+    pub commands: Vec<String>,
+    /// Undone entries are kept for the record.
+    pub undone_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournaledFile {
+    pub path: String,
+    /// The backup copy, relative to the entry directory.
+    /// `None` when the file did not exist before the fix, so undoing removes it.
+    pub backup: Option<String>,
+}
+
+/// Selects the journal entry to undo.
+pub enum UndoTarget {
+    /// The most recent entry that was not undone yet.
+    Last,
+    Id(String),
+}
+
+/// The persistent record of the fixes applied to a system, kept under [`STATE_DIR`].
+pub struct Journal<'a> {
+    root: &'a SystemRoot,
+}
+
+impl<'a> Journal<'a> {
+    pub fn new(root: &'a SystemRoot) -> Self {
+        Journal { root }
+    }
+
+    fn dir(&self) -> PathBuf {
+        self.root.path(STATE_DIR).join("journal")
+    }
+
+    /// Stores the backups of a successful fix and the commands it ran.
+    /// The entry file is written last, so an interrupted recording leaves no entry behind.
+    pub fn record(&self, requirement: &str, transaction: &Transaction, changes: &[Change]) -> Result<JournalEntry, Box<dyn Error>> {
+        let timestamp = Utc::now();
+        let base_id = format!("{}-{}", timestamp.format("%Y%m%d-%H%M%S"), requirement);
+        let mut id = base_id.clone();
+        let mut suffix = 1;
+        while self.dir().join(&id).exists() {
+            suffix += 1;
+            id = format!("{}-{}", base_id, suffix);
+        }
+
+        let entry_dir = self.dir().join(&id);
+        create_dir_all(&entry_dir.join("files"))?;
+
+        let mut files = vec![];
+        for (index, (path, backup)) in transaction.backups().enumerate() {
+            let backup = match backup {
+                Some(content) => {
+                    let name = format!("files/{}", index);
+                    write(&entry_dir.join(&name), content)?;
+                    Some(name)
+                }
+                None => None,
+            };
+            files.push(JournaledFile { path: path.to_string(), backup });
+        }
+
+        let commands = changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::RunCommand { command } => Some(command.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let entry = JournalEntry { id, timestamp, requirement: requirement.to_string(), files, commands, undone_at: None };
+        self.save(&entry)?;
+        Ok(entry)
+    }
+
+    /// Returns every entry, oldest first.
+    pub fn entries(&self) -> Result<Vec<JournalEntry>, Box<dyn Error>> {
+        let dir = self.dir();
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut entries = vec![];
+        for dir_entry in fs::read_dir(&dir).map_err(|e| FileError::new("read directory", &dir.to_string_lossy(), e))? {
+            let entry_file = dir_entry?.path().join(ENTRY_FILE);
+            if !entry_file.is_file() {
+                continue;
+            }
+            let content = fs::read_to_string(&entry_file)
+                .map_err(|e| FileError::new("read", &entry_file.to_string_lossy(), e))?;
+            let entry: JournalEntry = serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid journal entry {}: {}", entry_file.display(), e))?;
+            entries.push(entry);
+        }
+
+        entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.id.cmp(&b.id)));
+        Ok(entries)
+    }
+
+    /// Restores the files changed by a fix to their content before it.
+    ///
+    /// An entry can only be undone when no later fix changed the same files,
+    /// otherwise restoring them would also discard the later fix.
+    pub fn undo(&self, target: &UndoTarget) -> Result<JournalEntry, Box<dyn Error>> {
+        let entries = self.entries()?;
+        let position = match target {
+            UndoTarget::Last => entries
+                .iter()
+                .rposition(|entry| entry.undone_at.is_none())
+                .ok_or("There is no fix to undo")?,
+            UndoTarget::Id(id) => entries
+                .iter()
+                .position(|entry| &entry.id == id)
+                .ok_or_else(|| format!("Unknown journal entry: {}", id))?,
+        };
+
+        let mut entry = entries[position].clone();
+        if let Some(undone_at) = entry.undone_at {
+            return Err(format!("Journal entry {} was already undone at {}", entry.id, undone_at).into());
+        }
+
+        let later = entries[position + 1..].iter().filter(|later| later.undone_at.is_none()).find(|later| {
+            later.files.iter().any(|file| entry.files.iter().any(|own| own.path == file.path))
+        });
+        if let Some(later) = later {
+            return Err(format!("Journal entry {} changed the same files later, undo it first", later.id).into());
+        }
+
+        let entry_dir = self.dir().join(&entry.id);
+        for file in entry.files.iter().rev() {
+            restore(self.root, &entry_dir, file)?;
+            log::info!("Restored {}", file.path);
+        }
+
+        entry.undone_at = Some(Utc::now());
+        self.save(&entry)?;
+        Ok(entry)
+    }
+
+    fn save(&self, entry: &JournalEntry) -> Result<(), Box<dyn Error>> {
+        let entry_dir = self.dir().join(&entry.id);
+        let tmp = entry_dir.join(format!("{}.tmp", ENTRY_FILE));
+        write(&tmp, serde_json::to_string_pretty(entry)?.as_bytes())?;
+
+        let entry_file = entry_dir.join(ENTRY_FILE);
+        fs::rename(&tmp, &entry_file).map_err(|e| FileError::new("write", &entry_file.to_string_lossy(), e))?;
+        Ok(())
+    }
+}
+
+fn restore(root: &SystemRoot, entry_dir: &Path, file: &JournaledFile) -> Result<(), FileError> {
+    let path = root.path(&file.path);
+    match &file.backup {
+        Some(backup) => {
+            let backup = entry_dir.join(backup);
+            let content = fs::read(&backup).map_err(|e| FileError::new("read backup", &backup.to_string_lossy(), e))?;
+            write(&path, &content)
+        }
+        None => match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(FileError::new("remove", &file.path, e)),
+            _ => Ok(()),
+        },
+    }
+}
+
+fn create_dir_all(path: &Path) -> Result<(), FileError> {
+    fs::create_dir_all(path).map_err(|e| FileError::new("create directory", &path.to_string_lossy(), e))
+}
+
+fn write(path: &Path, content: &[u8]) -> Result<(), FileError> {
+    fs::write(path, content).map_err(|e| FileError::new("write", &path.to_string_lossy(), e))
+}
+
+#[test]
+fn test_undo_restores_files_and_keeps_entry() {
+    use crate::requirements::FixContext;
+
+    let root = crate::tests::fixture_root("journal-undo", &[("/etc/default/grub", "GRUB_CMDLINE_LINUX=\"\"\n")]);
+    let mut ctx = FixContext::new(&root, false);
+    ctx.write_file("/etc/default/grub", "GRUB_CMDLINE_LINUX=\"console=ttyS0\"\n").unwrap();
+    let recorded = ctx.commit("kernel-args").unwrap();
+
+    let journal = Journal::new(&root);
+    let undone = journal.undo(&UndoTarget::Last).unwrap();
+
+    assert_eq!(undone.id, recorded.id);
+    assert_eq!(root.read_to_string("/etc/default/grub").unwrap(), "GRUB_CMDLINE_LINUX=\"\"\n");
+    assert!(journal.entries().unwrap()[0].undone_at.is_some());
+    assert!(journal.undo(&UndoTarget::Last).is_err());
+}
+
+#[test]
+fn test_undo_refuses_entry_overwritten_by_later_fix() {
+    use crate::requirements::FixContext;
+
+    let root = crate::tests::fixture_root("journal-order", &[("/etc/default/grub", "GRUB_CMDLINE_LINUX=\"\"\n")]);
+    let mut first = FixContext::new(&root, false);
+    first.write_file("/etc/default/grub", "GRUB_CMDLINE_LINUX=\"nofb\"\n").unwrap();
+    let first = first.commit("kernel-args").unwrap();
+    let mut second = FixContext::new(&root, false);
+    second.write_file("/etc/default/grub", "GRUB_CMDLINE_LINUX=\"nofb console=ttyS0\"\n").unwrap();
+    let second = second.commit("kernel-args").unwrap();
+
+    let journal = Journal::new(&root);
+
+    assert_ne!(first.id, second.id);
+    assert!(journal.undo(&UndoTarget::Id(first.id.clone())).is_err());
+    journal.undo(&UndoTarget::Id(second.id)).unwrap();
+    journal.undo(&UndoTarget::Id(first.id)).unwrap();
+    assert_eq!(root.read_to_string("/etc/default/grub").unwrap(), "GRUB_CMDLINE_LINUX=\"\"\n");
+}
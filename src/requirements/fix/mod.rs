@@ -0,0 +1,146 @@
+use std::error::Error;
+
+use serde::Serialize;
+use similar::TextDiff;
+
+use crate::requirements::SystemRoot;
+
+pub use journal::{Journal, JournalEntry, UndoTarget};
+pub use transaction::{RolledBack, Transaction};
+
+pub mod journal;
+pub mod transaction;
+
+/// Everything a fix needs to change the target system.
+///
+/// Fixes must not write files or run commands on their own: going through the context
+/// lets `--dry-run` report every change as a unified diff or a command line instead,
+/// and records how to undo each change, so a failing fix can be rolled back.
+pub struct FixContext<'a> {
+    root: &'a SystemRoot,
+    dry_run: bool,
+    changes: Vec<Change>,
+    transaction: Transaction,
+}
+
+/// A change made, or planned in dry-run mode, by a fix.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    WriteFile { path: String, diff: String },
+    RunCommand { command: String },
+}
+
+impl<'a> FixContext<'a> {
+    pub fn new(root: &'a SystemRoot, dry_run: bool) -> Self {
+        FixContext { root, dry_run, changes: vec![], transaction: Transaction::new() }
+    }
+
+    pub fn root(&self) -> &SystemRoot {
+        self.root
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Records the applied fix in the journal of the target system, so it can be undone later.
+    pub fn commit(self, requirement: &str) -> Result<JournalEntry, Box<dyn Error>> {
+        Journal::new(self.root).record(requirement, &self.transaction, &self.changes)
+    }
+
+    /// Undoes every change made so far, in reverse order.
+    pub fn rollback(self) -> Vec<RolledBack> {
+        self.transaction.rollback(self.root)
+    }
+
+    /// Replaces the content of a file of the target system.
+    pub fn write_file(&mut self, path: &str, contents: &str) -> Result<(), Box<dyn Error>> {
+        let current = if self.root.path(path).exists() {
+            self.root.read_to_string(path)?
+        } else {
+            String::new()
+        };
+
+        let diff = TextDiff::from_lines(current.as_str(), contents)
+            .unified_diff()
+            .header(&format!("a{}", path), &format!("b{}", path))
+            .to_string();
+
+        if !self.dry_run {
+            log::info!("Writing {}", path);
+            self.transaction.backup_file(self.root, path)?;
+            self.root.write(path, contents)?;
+        }
+
+        self.changes.push(Change::WriteFile { path: path.to_string(), diff });
+        Ok(())
+    }
+
+    /// Backs up a file that a command run by the fix is going to modify,
+    /// e.g. `/boot/grub/grub.cfg` before `update-grub`, so it is restored on rollback.
+    pub fn backup_file(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        if !self.dry_run {
+            self.transaction.backup_file(self.root, path)?;
+        }
+        Ok(())
+    }
+
+    /// Runs a command inside the target system and fails if it exits with a non-zero status.
+    pub fn run_command(&mut self, program: &str, args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let command = std::iter::once(program).chain(args.iter().copied()).collect::<Vec<&str>>().join(" ");
+
+        if !self.dry_run {
+            log::info!("Running {}", command);
+            let output = self.root.command(program).args(args).output()
+                .map_err(|e| format!("Failed to run {}: {}", command, e))?;
+
+            if !output.status.success() {
+                let err_msg = String::from_utf8_lossy(&output.stderr);
+                return Err(format!("{} failed: {}", command, err_msg.trim()).into());
+            }
+        }
+
+        self.changes.push(Change::RunCommand { command });
+        Ok(())
+    }
+}
+
+#[test]
+fn test_dry_run_records_diff_without_writing() {
+    let root = crate::tests::fixture_root("fix-dry-run", &[("/etc/default/grub", "GRUB_TIMEOUT=5\nGRUB_CMDLINE_LINUX=\"\"\n")]);
+    let mut ctx = FixContext::new(&root, true);
+
+    ctx.write_file("/etc/default/grub", "GRUB_TIMEOUT=5\nGRUB_CMDLINE_LINUX=\"console=ttyS0\"\n").unwrap();
+    ctx.run_command("update-grub", &[]).unwrap();
+
+    assert_eq!(root.read_to_string("/etc/default/grub").unwrap(), "GRUB_TIMEOUT=5\nGRUB_CMDLINE_LINUX=\"\"\n");
+    match ctx.changes() {
+        [Change::WriteFile { path, diff }, Change::RunCommand { command }] => {
+            assert_eq!(path, "/etc/default/grub");
+            assert!(diff.starts_with("--- a/etc/default/grub\n+++ b/etc/default/grub\n"));
+            assert!(diff.contains("-GRUB_CMDLINE_LINUX=\"\"\n+GRUB_CMDLINE_LINUX=\"console=ttyS0\"\n"));
+            assert_eq!(command, "update-grub");
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+}
+
+#[test]
+fn test_rollback_after_failed_step() {
+    let root = crate::tests::fixture_root("fix-rollback", &[("/etc/default/grub", "GRUB_CMDLINE_LINUX=\"\"\n")]);
+    let mut ctx = FixContext::new(&root, false);
+
+    ctx.write_file("/etc/default/grub", "GRUB_CMDLINE_LINUX=\"console=ttyS0\"\n").unwrap();
+    assert!(ctx.run_command("/nonexistent/update-grub", &[]).is_err());
+
+    let rolled_back = ctx.rollback();
+
+    assert_eq!(rolled_back.len(), 1);
+    assert_eq!(rolled_back[0].action, "restore /etc/default/grub");
+    assert_eq!(root.read_to_string("/etc/default/grub").unwrap(), "GRUB_CMDLINE_LINUX=\"\"\n");
+}
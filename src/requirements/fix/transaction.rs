@@ -0,0 +1,122 @@
+use std::fs;
+
+use serde::Serialize;
+
+use crate::requirements::SystemRoot;
+use crate::utils::FileError;
+
+/// Undoes one step of a fix.
+#[derive(Debug, Clone)]
+pub enum Compensation {
+    /// Restores the previous content of a file, or removes it if it did not exist.
+    RestoreFile { path: String, backup: Option<Vec<u8>> },
+}
+
+impl Compensation {
+    fn describe(&self) -> String {
+        match self {
+            Compensation::RestoreFile { path, backup: Some(_) } => format!("restore {}", path),
+            Compensation::RestoreFile { path, backup: None } => format!("remove {}", path),
+        }
+    }
+
+    fn apply(&self, root: &SystemRoot) -> Result<(), String> {
+        match self {
+            Compensation::RestoreFile { path, backup: Some(content) } => fs::write(root.path(path), content)
+                .map_err(|e| FileError::new("restore", path, e).to_string()),
+            Compensation::RestoreFile { path, backup: None } => match fs::remove_file(root.path(path)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(FileError::new("remove", path, e).to_string()),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+/// A compensating action that was executed while rolling back a fix.
+#[derive(Debug, Clone, Serialize)]
+pub struct RolledBack {
+    pub action: String,
+    /// Set when the action failed, so the file or setting is left as the fix changed it.
+    pub error: Option<String>,
+}
+
+/// Records how to undo every step of a fix, so a failure at any step can
+/// restore the target system to the state it was in before the fix started.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    compensations: Vec<Compensation>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Transaction::default()
+    }
+
+    /// Keeps the current content of a file so it can be restored. Only the first backup
+    /// of a path is kept, since it is the one holding the state before the fix.
+    pub fn backup_file(&mut self, root: &SystemRoot, path: &str) -> Result<(), FileError> {
+        let already_backed_up = self.compensations.iter().any(|compensation| {
+            matches!(compensation, Compensation::RestoreFile { path: backed_up, .. } if backed_up == path)
+        });
+        if already_backed_up {
+            return Ok(());
+        }
+
+        let host_path = root.path(path);
+        let backup = if host_path.exists() {
+            Some(fs::read(&host_path).map_err(|e| FileError::new("back up", path, e))?)
+        } else {
+            None
+        };
+
+        self.compensations.push(Compensation::RestoreFile { path: path.to_string(), backup });
+        Ok(())
+    }
+
+    /// The backed up files, in the order they were first backed up, with their content
+    /// before the fix. The content is `None` when the file did not exist.
+    pub fn backups(&self) -> impl Iterator<Item = (&str, Option<&[u8]>)> {
+        self.compensations.iter().map(|compensation| match compensation {
+            Compensation::RestoreFile { path, backup } => (path.as_str(), backup.as_deref()),
+        })
+    }
+
+    /// Runs the compensating actions in reverse order. A failing action does not stop
+    /// the rollback, the remaining ones are still executed.
+    pub fn rollback(self, root: &SystemRoot) -> Vec<RolledBack> {
+        self.compensations
+            .iter()
+            .rev()
+            .map(|compensation| {
+                let action = compensation.describe();
+                let error = compensation.apply(root).err();
+                match &error {
+                    None => log::info!("Rolled back: {}", action),
+                    Some(e) => log::error!("Failed to roll back ({}): {}", action, e),
+                }
+                RolledBack { action, error }
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_rollback_restores_files_in_reverse_order() {
+    let root = crate::tests::fixture_root("transaction-rollback", &[("/etc/default/grub", "GRUB_CMDLINE_LINUX=\"\"\n")]);
+    let mut transaction = Transaction::new();
+
+    transaction.backup_file(&root, "/etc/default/grub").unwrap();
+    root.write("/etc/default/grub", "GRUB_CMDLINE_LINUX=\"console=ttyS0\"\n").unwrap();
+    transaction.backup_file(&root, "/etc/default/grub").unwrap();
+    transaction.backup_file(&root, "/etc/dracut.conf.d/virtio.conf").unwrap();
+    std::fs::create_dir_all(root.path("/etc/dracut.conf.d")).unwrap();
+    root.write("/etc/dracut.conf.d/virtio.conf", "add_drivers+=\" virtio_blk \"\n").unwrap();
+
+    let rolled_back = transaction.rollback(&root);
+
+    let actions: Vec<&str> = rolled_back.iter().map(|rolled_back| rolled_back.action.as_str()).collect();
+    assert_eq!(actions, vec!["remove /etc/dracut.conf.d/virtio.conf", "restore /etc/default/grub"]);
+    assert!(rolled_back.iter().all(|rolled_back| rolled_back.error.is_none()));
+    assert_eq!(root.read_to_string("/etc/default/grub").unwrap(), "GRUB_CMDLINE_LINUX=\"\"\n");
+    assert!(!root.path("/etc/dracut.conf.d/virtio.conf").exists());
+}
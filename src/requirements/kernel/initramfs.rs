@@ -0,0 +1,200 @@
+use std::io;
+use std::io::Read;
+
+use flate2::read::MultiGzDecoder;
+use thiserror::Error;
+use xz2::read::XzDecoder;
+
+use crate::requirements::kernel::modules::module_name;
+use crate::requirements::SystemRoot;
+
+const CPIO_NEWC: &[u8] = b"070701";
+const CPIO_CRC: &[u8] = b"070702";
+const CPIO_HEADER_LEN: usize = 110;
+const CPIO_TRAILER: &str = "TRAILER!!!";
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Error, Debug)]
+pub enum InitramfsError {
+    #[error("Invalid cpio header at offset {0}")]
+    InvalidHeader(usize),
+    #[error("Truncated cpio archive at offset {0}")]
+    Truncated(usize),
+    #[error("Unsupported initramfs compression: {0}")]
+    UnsupportedCompression(&'static str),
+    #[error("Failed to decompress the initramfs")]
+    Decompress(#[source] io::Error),
+}
+
+/// Finds the initramfs of a kernel release, using the names of Debian and Ubuntu
+/// (`initrd.img-<release>`), RHEL and Rocky (`initramfs-<release>.img`) and SUSE (`initrd-<release>`).
+pub fn find(root: &SystemRoot, release: &str) -> Option<String> {
+    [
+        format!("/boot/initrd.img-{}", release),
+        format!("/boot/initramfs-{}.img", release),
+        format!("/boot/initrd-{}", release),
+    ]
+    .into_iter()
+    .find(|path| root.path(path).is_file())
+}
+
+/// Lists the names of the kernel modules an initramfs contains, e.g. `virtio_blk`.
+pub fn modules(data: &[u8]) -> Result<Vec<String>, InitramfsError> {
+    Ok(list_files(data)?
+        .iter()
+        .filter(|path| path.contains("/modules/") && path.contains(".ko"))
+        .filter_map(|path| module_name(path))
+        .collect())
+}
+
+/// Lists the files of an initramfs. An initramfs is a sequence of cpio archives in the
+/// `newc` format: uncompressed ones first, e.g. the early microcode, then usually a
+/// compressed one with the actual root filesystem.
+pub fn list_files(data: &[u8]) -> Result<Vec<String>, InitramfsError> {
+    let mut files = vec![];
+    let mut pos = 0;
+
+    while pos < data.len() {
+        // archives are padded with zeros, e.g. to a 512 bytes boundary
+        if data[pos] == 0 {
+            pos += 1;
+            continue;
+        }
+
+        let rest = &data[pos..];
+        if rest.starts_with(CPIO_NEWC) || rest.starts_with(CPIO_CRC) {
+            pos = read_archive(data, pos, &mut files)?;
+        } else {
+            files.extend(list_files(&decompress(rest)?)?);
+            break;
+        }
+    }
+
+    Ok(files)
+}
+
+fn decompress(data: &[u8]) -> Result<Vec<u8>, InitramfsError> {
+    let mut decompressed = vec![];
+    let result = if data.starts_with(GZIP_MAGIC) {
+        MultiGzDecoder::new(data).read_to_end(&mut decompressed)
+    } else if data.starts_with(XZ_MAGIC) {
+        XzDecoder::new(data).read_to_end(&mut decompressed)
+    } else if data.starts_with(ZSTD_MAGIC) {
+        ruzstd::decoding::StreamingDecoder::new(data)
+            .map_err(io::Error::other)
+            .and_then(|mut decoder| decoder.read_to_end(&mut decompressed))
+    } else {
+        return Err(InitramfsError::UnsupportedCompression(compression_name(data)));
+    };
+
+    result.map_err(InitramfsError::Decompress)?;
+    Ok(decompressed)
+}
+
+fn compression_name(data: &[u8]) -> &'static str {
+    if data.starts_with(b"BZh") {
+        "bzip2"
+    } else if data.starts_with(&[0x02, 0x21, 0x4c, 0x18]) || data.starts_with(&[0x04, 0x22, 0x4d, 0x18]) {
+        "lz4"
+    } else if data.starts_with(&[0x5d, 0x00, 0x00]) {
+        "lzma"
+    } else if data.starts_with(&[0x89, b'L', b'Z', b'O']) {
+        "lzo"
+    } else {
+        "unknown"
+    }
+}
+
+/// Reads the entries of one cpio archive up to its trailer, returning the offset after it.
+fn read_archive(data: &[u8], mut pos: usize, files: &mut Vec<String>) -> Result<usize, InitramfsError> {
+    loop {
+        let header = data.get(pos..pos + CPIO_HEADER_LEN).ok_or(InitramfsError::Truncated(pos))?;
+        if !(header.starts_with(CPIO_NEWC) || header.starts_with(CPIO_CRC)) {
+            return Err(InitramfsError::InvalidHeader(pos));
+        }
+
+        // the fields after the magic are 8 hexadecimal digits each
+        let field = |index: usize| -> Result<usize, InitramfsError> {
+            let start = 6 + index * 8;
+            std::str::from_utf8(&header[start..start + 8])
+                .ok()
+                .and_then(|hex| usize::from_str_radix(hex, 16).ok())
+                .ok_or(InitramfsError::InvalidHeader(pos))
+        };
+        let file_size = field(6)?;
+        let name_size = field(11)?;
+
+        let name_start = pos + CPIO_HEADER_LEN;
+        let name = data.get(name_start..name_start + name_size).ok_or(InitramfsError::Truncated(name_start))?;
+        let name = String::from_utf8_lossy(name.strip_suffix(&[0]).unwrap_or(name)).to_string();
+
+        let data_start = align4(name_start + name_size);
+        pos = align4(data_start + file_size);
+        if pos > align4(data.len()) {
+            return Err(InitramfsError::Truncated(data_start));
+        }
+
+        if name == CPIO_TRAILER {
+            return Ok(pos.min(data.len()));
+        }
+        files.push(name);
+    }
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+/// Builds a cpio archive in the `newc` format, with empty files.
+#[cfg(test)]
+pub(crate) fn cpio(names: &[&str]) -> Vec<u8> {
+    let mut archive = vec![];
+    for name in names.iter().chain(std::iter::once(&CPIO_TRAILER)) {
+        archive.extend(format!("070701{:08x}{:08x}", 0, 0o100644).as_bytes());
+        archive.extend(format!("{:08x}", 0).repeat(4).as_bytes());
+        archive.extend(format!("{:08x}", 0).repeat(5).as_bytes());
+        archive.extend(format!("{:08x}{:08x}", name.len() + 1, 0).as_bytes());
+        archive.extend(name.as_bytes());
+        archive.push(0);
+        archive.resize(align4(archive.len()), 0);
+    }
+    archive
+}
+
+#[test]
+fn test_modules_of_microcode_prefixed_images() {
+    use std::io::Write;
+
+    let early = cpio(&["kernel", "kernel/x86/microcode/GenuineIntel.bin"]);
+    let main = cpio(&[
+        "usr/lib/modules/5.15.0-91-generic/kernel/drivers/block/virtio_blk.ko.zst",
+        "usr/lib/modules/5.15.0-91-generic/kernel/drivers/net/virtio_net.ko",
+        "usr/bin/busybox",
+    ]);
+
+    let mut gzip = flate2::write::GzEncoder::new(vec![], flate2::Compression::fast());
+    gzip.write_all(&main).unwrap();
+    let mut xz = xz2::write::XzEncoder::new(vec![], 1);
+    xz.write_all(&main).unwrap();
+    let zstd = ruzstd::encoding::compress_to_vec(main.as_slice(), ruzstd::encoding::CompressionLevel::Fastest);
+
+    for compressed in [gzip.finish().unwrap(), xz.finish().unwrap(), zstd] {
+        let mut image = early.clone();
+        image.resize(512, 0);
+        image.extend(compressed);
+
+        assert_eq!(modules(&image).unwrap(), vec!["virtio_blk", "virtio_net"]);
+    }
+    assert_eq!(list_files(&main).unwrap().len(), 3);
+}
+
+#[test]
+fn test_unsupported_compression() {
+    let mut image = cpio(&["kernel"]);
+    image.extend(b"BZh91AY&SY");
+
+    assert!(matches!(list_files(&image), Err(InitramfsError::UnsupportedCompression("bzip2"))));
+}
@@ -0,0 +1,158 @@
+use std::error::Error;
+use std::fmt::Debug;
+
+use thiserror::Error;
+
+use crate::requirements::kernel::initramfs;
+use crate::requirements::{FixContext, SystemRoot};
+
+pub const INITRAMFS_TOOLS_MODULES: &str = "/etc/initramfs-tools/modules";
+pub const DRACUT_CONF: &str = "/etc/dracut.conf.d/90-vpc-migration-tools.conf";
+
+#[derive(Error, Debug)]
+pub enum InitramfsGeneratorError {
+    #[error("No supported initramfs tooling found, looked for update-initramfs and dracut")]
+    NotFound,
+}
+
+/// The distribution tooling that builds the initramfs of the installed kernels.
+pub trait InitramfsGenerator: Debug {
+    fn name(&self) -> &'static str;
+    /// Configures modules to include in the initramfs of every kernel, including the kernels installed later.
+    fn add_modules(&self, ctx: &mut FixContext, modules: &[&str]) -> Result<(), Box<dyn Error>>;
+    /// Rebuilds the initramfs of a kernel release. The previous image is backed up through the context.
+    fn regenerate(&self, ctx: &mut FixContext, release: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// Detects the initramfs tooling of the target system: `update-initramfs` of initramfs-tools
+/// (Debian, Ubuntu), otherwise `dracut` (RHEL, Rocky, SUSE).
+pub fn detect(root: &SystemRoot) -> Result<Box<dyn InitramfsGenerator>, InitramfsGeneratorError> {
+    if root.path("/etc/initramfs-tools").is_dir() {
+        if let Some(program) = root.find_program("update-initramfs") {
+            return Ok(Box::new(InitramfsTools { program }));
+        }
+    }
+
+    root.find_program("dracut")
+        .map(|program| Box::new(Dracut { program }) as Box<dyn InitramfsGenerator>)
+        .ok_or(InitramfsGeneratorError::NotFound)
+}
+
+/// initramfs-tools loads the modules listed one per line in `/etc/initramfs-tools/modules`.
+#[derive(Debug)]
+pub struct InitramfsTools {
+    program: String,
+}
+
+impl InitramfsGenerator for InitramfsTools {
+    fn name(&self) -> &'static str {
+        "update-initramfs"
+    }
+
+    fn add_modules(&self, ctx: &mut FixContext, modules: &[&str]) -> Result<(), Box<dyn Error>> {
+        let path = INITRAMFS_TOOLS_MODULES;
+        let mut content = if ctx.root().path(path).exists() {
+            ctx.root().read_to_string(path)?
+        } else {
+            String::new()
+        };
+
+        let listed: Vec<String> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_whitespace().next())
+            .map(|module| module.replace('-', "_"))
+            .collect();
+        let missing: Vec<&str> = modules.iter().copied().filter(|module| !listed.iter().any(|listed| listed == module)).collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        for module in missing {
+            content.push_str(module);
+            content.push('\n');
+        }
+        ctx.write_file(path, &content)
+    }
+
+    fn regenerate(&self, ctx: &mut FixContext, release: &str) -> Result<(), Box<dyn Error>> {
+        let image = format!("/boot/initrd.img-{}", release);
+        let mode = if ctx.root().path(&image).exists() { "-u" } else { "-c" };
+
+        ctx.backup_file(&image)?;
+        ctx.run_command(&self.program, &[mode, "-k", release])
+            .map_err(|e| format!("Failed to regenerate the initramfs of {}: {}", release, e))?;
+        Ok(())
+    }
+}
+
+/// dracut reads `add_drivers` from the `*.conf` files of `/etc/dracut.conf.d`. SUSE uses dracut
+/// as well, with its images named `initrd-<release>` instead of `initramfs-<release>.img`.
+#[derive(Debug)]
+pub struct Dracut {
+    program: String,
+}
+
+impl InitramfsGenerator for Dracut {
+    fn name(&self) -> &'static str {
+        "dracut"
+    }
+
+    fn add_modules(&self, ctx: &mut FixContext, modules: &[&str]) -> Result<(), Box<dyn Error>> {
+        let content = format!(
+            "# Added by vpc-migration-tools, the drivers needed to boot on VPC\nadd_drivers+=\" {} \"\n",
+            modules.join(" ")
+        );
+        if ctx.root().path(DRACUT_CONF).exists() && ctx.root().read_to_string(DRACUT_CONF)? == content {
+            return Ok(());
+        }
+        ctx.write_file(DRACUT_CONF, &content)
+    }
+
+    fn regenerate(&self, ctx: &mut FixContext, release: &str) -> Result<(), Box<dyn Error>> {
+        let image = initramfs::find(ctx.root(), release).unwrap_or_else(|| format!("/boot/initramfs-{}.img", release));
+
+        ctx.backup_file(&image)?;
+        ctx.run_command(&self.program, &["--force", &image, release])
+            .map_err(|e| format!("Failed to regenerate the initramfs of {}: {}", release, e))?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_detect_initramfs_generator() {
+    let debian = crate::tests::fixture_root("initramfs-tools", &[
+        ("/usr/sbin/update-initramfs", ""),
+        ("/usr/bin/dracut", ""),
+        ("/etc/initramfs-tools/modules", "# List of modules\n"),
+    ]);
+    let suse = crate::tests::fixture_root("initramfs-dracut", &[("/usr/bin/dracut", "")]);
+
+    assert_eq!(detect(&debian).unwrap().name(), "update-initramfs");
+    assert_eq!(detect(&suse).unwrap().name(), "dracut");
+    assert!(detect(&crate::tests::fixture_root("initramfs-none", &[])).is_err());
+}
+
+#[test]
+fn test_initramfs_tools_lists_missing_modules_once() {
+    use crate::requirements::fix::Change;
+
+    let root = crate::tests::fixture_root("initramfs-tools-modules", &[
+        ("/etc/initramfs-tools/modules", "# List of modules\nvirtio-pci\n"),
+    ]);
+    let generator = InitramfsTools { program: "/usr/sbin/update-initramfs".to_string() };
+    let mut ctx = FixContext::new(&root, true);
+
+    generator.add_modules(&mut ctx, &["virtio_pci", "virtio_blk", "virtio_net"]).unwrap();
+
+    match ctx.changes() {
+        [Change::WriteFile { diff, .. }] => {
+            assert!(diff.contains(" virtio-pci\n+virtio_blk\n+virtio_net\n"));
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+}
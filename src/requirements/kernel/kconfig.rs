@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+
+use crate::requirements::kernel::DriverState;
+use crate::utils::FileError;
+
+pub const PROC_CONFIG: &str = "/proc/config.gz";
+
+/// The Kconfig of a kernel build, as found in `/boot/config-<release>` or `/proc/config.gz`.
+#[derive(Debug, Default)]
+pub struct KernelConfig {
+    options: HashMap<String, String>,
+}
+
+impl KernelConfig {
+    /// Parses `CONFIG_NAME=value` lines. Options commented out as `# CONFIG_NAME is not set`
+    /// and other comments are skipped, so they read as unset.
+    pub fn parse(content: &str) -> Self {
+        let options = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(name, value)| (name.to_string(), value.trim_matches('"').to_string()))
+            .collect();
+
+        KernelConfig { options }
+    }
+
+    /// Reads the configuration of the running kernel, exposed by `CONFIG_IKCONFIG_PROC`.
+    pub fn from_proc() -> Result<Self, FileError> {
+        let file = File::open(PROC_CONFIG).map_err(|e| FileError::new("open", PROC_CONFIG, e))?;
+        let mut content = String::new();
+        GzDecoder::new(file)
+            .read_to_string(&mut content)
+            .map_err(|e| FileError::new("decompress", PROC_CONFIG, e))?;
+        Ok(KernelConfig::parse(&content))
+    }
+
+    pub fn get(&self, option: &str) -> Option<&str> {
+        self.options.get(option).map(String::as_str)
+    }
+
+    /// Tells how the driver of a tristate option, e.g. `CONFIG_VIRTIO_BLK`, is built.
+    pub fn driver_state(&self, option: &str) -> DriverState {
+        match self.get(option) {
+            Some("y") => DriverState::BuiltIn,
+            Some("m") => DriverState::Module,
+            _ => DriverState::Missing,
+        }
+    }
+}
+
+#[test]
+fn test_parse_tristate_options() {
+    let config = KernelConfig::parse(
+        "#\n# Automatically generated file; DO NOT EDIT.\n#\nCONFIG_VIRTIO_BLK=y\nCONFIG_VIRTIO_NET=m\n\
+         # CONFIG_VIRTIO_CONSOLE is not set\nCONFIG_LOCALVERSION=\"-generic\"\nCONFIG_VIRTIO_PCI=n\n",
+    );
+
+    assert_eq!(config.driver_state("CONFIG_VIRTIO_BLK"), DriverState::BuiltIn);
+    assert_eq!(config.driver_state("CONFIG_VIRTIO_NET"), DriverState::Module);
+    assert_eq!(config.driver_state("CONFIG_VIRTIO_CONSOLE"), DriverState::Missing);
+    assert_eq!(config.driver_state("CONFIG_VIRTIO_PCI"), DriverState::Missing);
+    assert_eq!(config.driver_state("CONFIG_SCSI_VIRTIO"), DriverState::Missing);
+    assert_eq!(config.get("CONFIG_LOCALVERSION"), Some("-generic"));
+}
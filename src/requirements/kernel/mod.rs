@@ -0,0 +1,15 @@
+use serde::Serialize;
+
+pub mod initramfs;
+pub mod initramfs_generator;
+pub mod kconfig;
+pub mod modules;
+
+/// How a kernel provides a driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriverState {
+    BuiltIn,
+    Module,
+    Missing,
+}
@@ -0,0 +1,61 @@
+use std::collections::HashSet;
+
+use crate::requirements::kernel::DriverState;
+use crate::requirements::SystemRoot;
+use crate::utils::FileError;
+
+/// The modules of a kernel release, from the indexes `depmod` writes in `/lib/modules/<release>`.
+#[derive(Debug, Default)]
+pub struct ModulesIndex {
+    builtin: HashSet<String>,
+    loadable: HashSet<String>,
+}
+
+impl ModulesIndex {
+    pub fn load(root: &SystemRoot, release: &str) -> Result<Self, FileError> {
+        let dir = format!("/lib/modules/{}", release);
+        let builtin = root.read_to_string(format!("{}/modules.builtin", dir))?;
+        let dep = root.read_to_string(format!("{}/modules.dep", dir))?;
+        Ok(ModulesIndex::parse(&builtin, &dep))
+    }
+
+    /// `modules.builtin` lists one module path per line, `modules.dep` one module path
+    /// followed by a colon and its dependencies per line.
+    pub fn parse(builtin: &str, dep: &str) -> Self {
+        ModulesIndex {
+            builtin: builtin.lines().filter_map(module_name).collect(),
+            loadable: dep.lines().filter_map(|line| line.split(':').next()).filter_map(module_name).collect(),
+        }
+    }
+
+    pub fn driver_state(&self, module: &str) -> DriverState {
+        if self.builtin.contains(module) {
+            DriverState::BuiltIn
+        } else if self.loadable.contains(module) {
+            DriverState::Module
+        } else {
+            DriverState::Missing
+        }
+    }
+}
+
+/// Turns a module path such as `kernel/drivers/block/virtio_blk.ko.zst` into the module name.
+/// Like `modprobe`, dashes and underscores are equivalent.
+pub fn module_name(path: &str) -> Option<String> {
+    let file_name = path.trim().rsplit('/').next()?;
+    let name = file_name.split(".ko").next().filter(|name| !name.is_empty())?;
+    Some(name.replace('-', "_"))
+}
+
+#[test]
+fn test_driver_state_from_indexes() {
+    let index = ModulesIndex::parse(
+        "kernel/drivers/virtio/virtio.ko\nkernel/drivers/virtio/virtio_pci.ko\n",
+        "kernel/drivers/block/virtio_blk.ko.zst: kernel/drivers/virtio/virtio_ring.ko\nkernel/drivers/net/virtio_net.ko.xz:\n",
+    );
+
+    assert_eq!(index.driver_state("virtio_pci"), DriverState::BuiltIn);
+    assert_eq!(index.driver_state("virtio_blk"), DriverState::Module);
+    assert_eq!(index.driver_state("virtio_net"), DriverState::Module);
+    assert_eq!(index.driver_state("virtio_scsi"), DriverState::Missing);
+}
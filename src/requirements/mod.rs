@@ -1,23 +1,86 @@
 use std::any::Any;
 use std::error::Error;
-use std::fmt::Debug;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
 
+use serde::Serialize;
+use serde_json::Value;
+
+pub use fix::FixContext;
+pub use system_root::SystemRoot;
+
+pub mod bootloader;
 pub mod checks;
+pub mod fix;
+pub mod kernel;
+pub mod network;
+pub mod os_matrix;
+pub mod os_release;
+pub mod preparation;
+pub mod report;
 pub mod run_requirements;
+pub mod run_undo;
+pub mod system_root;
+
+/// The IBM Cloud documentation the requirements are based on.
+pub const DOCUMENTATION_URL: &str = "https://cloud.ibm.com/docs/vpc?topic=vpc-create-linux-custom-image";
+
+/// How much a check result affects the readiness of the system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The image will not work on IBM Cloud VPC.
+    Blocker,
+    /// The image works, but some features (e.g. the serial console) will not.
+    Warning,
+    /// Nothing to act on, e.g. the requirement passed.
+    Info,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Blocker => write!(f, "blocker"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Info => write!(f, "info"),
+        }
+    }
+}
 
 pub trait CheckResult {
     fn passed(&self) -> bool;
     fn log(&self) -> ();
+    /// One-line description of the outcome, used as the message of report entries.
+    fn message(&self) -> String;
+    /// The values measured by the check, reported in machine-readable output.
+    fn details(&self) -> Value;
+    fn severity(&self) -> Severity;
+    /// What the operator should do to satisfy the requirement, when it failed.
+    fn remediation(&self) -> Option<String> {
+        None
+    }
+    /// The section of the IBM Cloud documentation describing the requirement.
+    fn documentation_url(&self) -> &'static str {
+        DOCUMENTATION_URL
+    }
 }
 
 pub trait Requirement: Debug {
     // type CheckResultType: CheckResult;
-    fn check(&self) -> Result<Box<dyn CheckResult>, Box<dyn Error>>;
-    fn fix(&self) -> Option<Result<(), Box<dyn Error>>> {
+    /// Stable identifier of the requirement, used in reports and on the command line.
+    fn name(&self) -> &'static str;
+    fn check(&self, root: &SystemRoot) -> Result<Box<dyn CheckResult>, Box<dyn Error>>;
+    /// Fixes the requirement. Every change must go through the context, so it can be previewed.
+    /// Returns false when there was nothing to change.
+    fn fix(&self, _ctx: &mut FixContext) -> Option<Result<bool, Box<dyn Error>>> {
         None
     }
     fn fixable(&self) -> bool {
         false
     }
+    /// True when the check observes the running system, so an applied fix
+    /// only makes it pass after a reboot.
+    fn takes_effect_after_reboot(&self, _root: &SystemRoot) -> bool {
+        false
+    }
 }
-
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::requirements::network::{config_files, normalize_mac, Addressing, Backend, InterfaceConfig};
+use crate::requirements::{FixContext, SystemRoot};
+use crate::utils::shell_assignments::ShellFile;
+
+/// Where RHEL can also set the default gateway, for every interface.
+pub const RHEL_NETWORK: &str = "/etc/sysconfig/network";
+/// The routes of SUSE, for every interface and per interface in `ifroute-<interface>`.
+pub const SUSE_ROUTES: &str = "/etc/sysconfig/network/routes";
+
+/// The `ifcfg-*` directories of RHEL and of SUSE, where wicked reads them.
+pub const IFCFG_DIRS: [(&str, Backend); 2] = [
+    ("/etc/sysconfig/network-scripts", Backend::Ifcfg),
+    ("/etc/sysconfig/network", Backend::Wicked),
+];
+
+/// Loads the `ifcfg-*` files of RHEL and SUSE.
+pub fn load(root: &SystemRoot) -> Result<Vec<InterfaceConfig>, Box<dyn Error>> {
+    let mut configs = vec![];
+    for (dir, backend) in IFCFG_DIRS {
+        let is_ifcfg = |name: &str| name.starts_with("ifcfg-") && !name.ends_with('~') && !name.contains(".rpm") && !name.ends_with(".bak");
+        for path in config_files(root, dir, is_ifcfg)? {
+            configs.push(parse(backend, &path, &root.read_to_string(&path)?)?);
+        }
+    }
+    Ok(configs)
+}
+
+/// Parses an `ifcfg-*` file, which the network scripts source.
+pub fn parse(backend: Backend, path: &str, content: &str) -> Result<InterfaceConfig, Box<dyn Error>> {
+    let file = ShellFile::parse(path, content, &mut HashMap::new())?;
+    let get = |key: &str| file.get(key).map(str::trim).filter(|value| !value.is_empty());
+
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let id = file_name.strip_prefix("ifcfg-").unwrap_or(file_name);
+
+    // RHEL numbers the addresses IPADDR0, IPADDR1..., SUSE names them IPADDR_0, IPADDR_home...
+    let addresses: Vec<String> = ["IPADDR", "IPADDR0", "IPADDR1", "IPADDR2", "IPADDR_0", "IPADDR_1", "IPADDR_2"]
+        .iter()
+        .filter_map(|key| get(key))
+        .map(str::to_string)
+        .collect();
+    let addressing = match get("BOOTPROTO").map(str::to_lowercase).as_deref() {
+        Some(bootproto) if bootproto.starts_with("dhcp") || bootproto == "bootp" => Addressing::Dhcp,
+        _ if !addresses.is_empty() => Addressing::Static,
+        _ => Addressing::Unconfigured,
+    };
+
+    let mut config = InterfaceConfig::new(backend, path, id, addressing);
+    config.name = Some(get("DEVICE").unwrap_or(id).to_string());
+    config.mac_address = get("HWADDR").or_else(|| get("LLADDR")).map(normalize_mac);
+    config.addresses = addresses;
+    config.gateway = get("GATEWAY").map(str::to_string);
+    config.dns = ["DNS1", "DNS2", "DNS3"].iter().filter_map(|key| get(key)).map(str::to_string).collect();
+    Ok(config)
+}
+
+/// Switches the interface to `BOOTPROTO=dhcp`, and drops its static addresses, gateway and DNS servers,
+/// including the default routes of SUSE and the global gateway of RHEL.
+pub fn to_dhcp(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    let mut file = ShellFile::parse(&config.path, &ctx.root().read_to_string(&config.path)?, &mut HashMap::new())?;
+    file.set("BOOTPROTO", "dhcp")?;
+    let static_keys: Vec<String> = file.keys().into_iter().filter(|key| is_static_key(key)).map(str::to_string).collect();
+    for key in static_keys {
+        file.remove(&key)?;
+    }
+    ctx.write_file(&config.path, file.content())?;
+
+    match config.backend {
+        Backend::Wicked => {
+            for routes in [SUSE_ROUTES.to_string(), format!("{}/ifroute-{}", IFCFG_DIRS[1].0, config.id)] {
+                if ctx.root().path(&routes).is_file() {
+                    let content = ctx.root().read_to_string(&routes)?;
+                    let kept = without_default_routes(&content, &config.id);
+                    if kept != content {
+                        ctx.write_file(&routes, &kept)?;
+                    }
+                }
+            }
+        }
+        _ if ctx.root().path(RHEL_NETWORK).is_file() => {
+            let mut network = ShellFile::parse(RHEL_NETWORK, &ctx.root().read_to_string(RHEL_NETWORK)?, &mut HashMap::new())?;
+            let gateway_device = network.get("GATEWAYDEV").map(str::to_string);
+            if network.get("GATEWAY").is_some() && gateway_device.as_deref().is_none_or(|device| Some(device) == config.name.as_deref()) {
+                network.remove("GATEWAY")?;
+                ctx.write_file(RHEL_NETWORK, network.content())?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Drops `HWADDR`, which binds the file to a NIC on RHEL, and `LLADDR`, which forces the MAC address on SUSE.
+pub fn remove_mac_binding(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    let mut file = ShellFile::parse(&config.path, &ctx.root().read_to_string(&config.path)?, &mut HashMap::new())?;
+    file.remove("HWADDR")?;
+    file.remove("LLADDR")?;
+    ctx.write_file(&config.path, file.content())
+}
+
+/// The keys of a static configuration, e.g. `IPADDR1`, `NETMASK_0` or `DNS2`.
+fn is_static_key(key: &str) -> bool {
+    let base = key.trim_end_matches(|c: char| c.is_ascii_digit()).split('_').next().unwrap_or(key);
+    matches!(base, "IPADDR" | "PREFIX" | "PREFIXLEN" | "NETMASK" | "BROADCAST" | "GATEWAY" | "DNS" | "PEERDNS")
+}
+
+/// Drops the default routes of a SUSE routes file that apply to the interface, the
+/// lines `default <gateway> - <interface>` or `0.0.0.0 <gateway> 0.0.0.0 -`.
+fn without_default_routes(content: &str, interface: &str) -> String {
+    content
+        .lines()
+        .filter(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let is_default = matches!(fields.first(), Some(&"default" | &"0.0.0.0" | &"0.0.0.0/0"));
+            let device = fields.get(3).copied().unwrap_or("-");
+            !(is_default && (device == "-" || device == interface))
+        })
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+#[test]
+fn test_parse_rhel_and_suse_ifcfg() {
+    let rhel = parse(
+        Backend::Ifcfg,
+        "/etc/sysconfig/network-scripts/ifcfg-eth0",
+        "TYPE=Ethernet\nBOOTPROTO=none\nDEVICE=eth0\nONBOOT=yes\nHWADDR=52:54:00:AB:CD:EF\nIPADDR=10.10.0.5\nPREFIX=24\nGATEWAY=10.10.0.1\nDNS1=10.10.0.2\n",
+    )
+    .unwrap();
+    let suse = parse(Backend::Wicked, "/etc/sysconfig/network/ifcfg-eth0", "BOOTPROTO='dhcp'\nSTARTMODE='auto'\n").unwrap();
+
+    assert_eq!(rhel.addressing, Addressing::Static);
+    assert_eq!(rhel.addresses, vec!["10.10.0.5"]);
+    assert_eq!(rhel.gateway.as_deref(), Some("10.10.0.1"));
+    assert_eq!(rhel.mac_address.as_deref(), Some("52:54:00:ab:cd:ef"));
+    assert_eq!(suse.addressing, Addressing::Dhcp);
+    assert_eq!(suse.name.as_deref(), Some("eth0"));
+}
+
+#[test]
+fn test_ifcfg_to_dhcp() {
+    let path = "/etc/sysconfig/network-scripts/ifcfg-eth0";
+    let root = crate::tests::fixture_root("ifcfg-to-dhcp", &[
+        (path, "TYPE=Ethernet\nBOOTPROTO=none\nDEVICE=eth0\nONBOOT=yes\nIPADDR=10.10.0.5\nPREFIX=24\nGATEWAY=10.10.0.1\nDNS1=10.10.0.2\n"),
+        (RHEL_NETWORK, "NETWORKING=yes\nGATEWAY=10.10.0.1\n"),
+    ]);
+    let config = load(&root).unwrap().remove(0);
+
+    to_dhcp(&mut FixContext::new(&root, false), &config).unwrap();
+
+    assert_eq!(root.read_to_string(path).unwrap(), "TYPE=Ethernet\nBOOTPROTO=\"dhcp\"\nDEVICE=eth0\nONBOOT=yes\n");
+    assert_eq!(root.read_to_string(RHEL_NETWORK).unwrap(), "NETWORKING=yes\n");
+    assert_eq!(without_default_routes("default 10.0.0.1 - -\n10.1.0.0/16 10.0.0.254 - eth0\n", "eth0"), "10.1.0.0/16 10.0.0.254 - eth0\n");
+}
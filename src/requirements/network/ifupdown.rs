@@ -0,0 +1,210 @@
+use std::error::Error;
+
+use crate::requirements::network::{config_files, glob_match, normalize_mac, Addressing, Backend, InterfaceConfig};
+use crate::requirements::{FixContext, SystemRoot};
+
+pub const INTERFACES: &str = "/etc/network/interfaces";
+
+/// Loads the `iface ... inet` stanzas of `/etc/network/interfaces` and of the files it sources.
+pub fn load(root: &SystemRoot) -> Result<Vec<InterfaceConfig>, Box<dyn Error>> {
+    let mut configs = vec![];
+    let mut pending = vec![INTERFACES.to_string()];
+    let mut visited: Vec<String> = vec![];
+
+    while let Some(path) = pending.pop() {
+        if visited.contains(&path) || !root.path(&path).is_file() {
+            continue;
+        }
+        let content = root.read_to_string(&path)?;
+        let (file_configs, sources) = parse(&path, &content);
+        configs.extend(file_configs);
+
+        // sourced files are read in place of the directive, so they go first
+        let mut sourced = vec![];
+        for source in sources {
+            sourced.extend(match source {
+                Source::Files(pattern) => sourced_files(root, &pattern)?,
+                Source::Directory(dir) => config_files(root, &dir, is_valid_part_name)?,
+            });
+        }
+        pending.extend(sourced.into_iter().rev());
+        visited.push(path);
+    }
+    Ok(configs)
+}
+
+/// A `source` or `source-directory` directive.
+#[derive(Debug, PartialEq)]
+enum Source {
+    Files(String),
+    Directory(String),
+}
+
+/// `source-directory` only reads files named like `run-parts` accepts them.
+fn is_valid_part_name(name: &str) -> bool {
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Expands the glob of a `source` directive, which only supports a glob in the file name.
+fn sourced_files(root: &SystemRoot, pattern: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    match pattern.rsplit_once('/') {
+        Some((dir, name)) if name.contains(['*', '?', '[']) => {
+            Ok(config_files(root, dir, |file_name| glob_match(name, file_name))?)
+        }
+        _ => Ok(vec![pattern.to_string()]),
+    }
+}
+
+/// Parses the stanzas of an interfaces file. The options of an `iface` stanza are the lines
+/// up to the next stanza, and lines ending with a backslash continue on the next line.
+fn parse(path: &str, content: &str) -> (Vec<InterfaceConfig>, Vec<Source>) {
+    let dir = path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("/etc/network");
+    let relative = |source: &str| if source.starts_with('/') { source.to_string() } else { format!("{}/{}", dir, source) };
+
+    let mut configs: Vec<InterfaceConfig> = vec![];
+    let mut sources = vec![];
+    let mut in_iface = false;
+
+    for line in content.replace("\\\n", " ").lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[0] {
+            "iface" if words.len() >= 4 && words[2] == "inet" => {
+                let addressing = match words[3] {
+                    "dhcp" | "bootp" => Addressing::Dhcp,
+                    "static" => Addressing::Static,
+                    _ => Addressing::Unconfigured,
+                };
+                let mut config = InterfaceConfig::new(Backend::Ifupdown, path, words[1], addressing);
+                config.name = Some(words[1].to_string());
+                configs.push(config);
+                in_iface = true;
+            }
+            "source" => sources.extend(words[1..].iter().map(|source| Source::Files(relative(source)))),
+            "source-directory" | "source-dir" => sources.extend(words[1..].iter().map(|dir| Source::Directory(relative(dir)))),
+            "iface" | "mapping" | "auto" | "rename" => in_iface = false,
+            keyword if keyword.starts_with("allow-") || keyword.starts_with("no-") => in_iface = false,
+            option if in_iface => {
+                let config = configs.last_mut().expect("an iface stanza is open");
+                let values = &words[1..];
+                match option {
+                    "address" => config.addresses.extend(values.iter().map(|value| value.to_string())),
+                    "gateway" => config.gateway = values.first().map(|value| value.to_string()),
+                    "dns-nameservers" => config.dns.extend(values.iter().map(|value| value.to_string())),
+                    "hwaddress" => config.mac_address = values.last().map(|mac| normalize_mac(mac)),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    (configs, sources)
+}
+
+/// The options of a static `iface` stanza, which `inet dhcp` does not take.
+const STATIC_OPTIONS: [&str; 8] = ["address", "netmask", "broadcast", "network", "gateway", "pointopoint", "dns-nameservers", "dns-search"];
+
+/// Switches the `iface <interface> inet` stanzas of the configuration file to `dhcp`,
+/// and drops their static options.
+pub fn to_dhcp(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    let content = ctx.root().read_to_string(&config.path)?;
+    ctx.write_file(&config.path, &stanza_to_dhcp(&content, &config.id))
+}
+
+/// Drops the `hwaddress` options of the `iface <interface> inet` stanzas, which force the MAC address of the interface.
+pub fn remove_mac_binding(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    let content = ctx.root().read_to_string(&config.path)?;
+    ctx.write_file(&config.path, &edit_stanza(&content, &config.id, None, &["hwaddress"]))
+}
+
+fn stanza_to_dhcp(content: &str, interface: &str) -> String {
+    edit_stanza(content, interface, Some("dhcp"), &STATIC_OPTIONS)
+}
+
+/// Edits the `iface <interface> inet` stanzas: switches them to the method, if any, and drops the options.
+fn edit_stanza(content: &str, interface: &str, method: Option<&str>, options: &[&str]) -> String {
+    let mut edited = String::new();
+    let mut in_stanza = false;
+
+    for line in content.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.first().copied() {
+            Some("iface") if words.len() >= 4 && words[1] == interface && words[2] == "inet" => {
+                in_stanza = true;
+                if let Some(method) = method {
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    edited.push_str(&format!("{}iface {} inet {}", indent, interface, method));
+                    for option in &words[4..] {
+                        edited.push(' ');
+                        edited.push_str(option);
+                    }
+                    edited.push('\n');
+                    continue;
+                }
+            }
+            Some("iface" | "mapping" | "auto" | "source" | "source-directory" | "source-dir" | "rename") => in_stanza = false,
+            Some(keyword) if keyword.starts_with("allow-") || keyword.starts_with("no-") => in_stanza = false,
+            Some(option) if in_stanza && options.contains(&option) => continue,
+            _ => {}
+        }
+        edited.push_str(line);
+        edited.push('\n');
+    }
+    edited
+}
+
+#[test]
+fn test_parse_interfaces_stanzas() {
+    let (configs, sources) = parse(
+        INTERFACES,
+        "source /etc/network/interfaces.d/*\n\nauto lo\niface lo inet loopback\n\n\
+         allow-hotplug ens3\niface ens3 inet static\n    address 192.168.1.10/24\n    gateway 192.168.1.1\n    hwaddress ether 52:54:00:AB:CD:EF\n\
+         iface ens3 inet6 auto\n\nauto ens4\niface ens4 inet dhcp\n",
+    );
+
+    assert_eq!(sources, vec![Source::Files("/etc/network/interfaces.d/*".to_string())]);
+    assert_eq!(configs.iter().map(|config| config.id.as_str()).collect::<Vec<&str>>(), vec!["lo", "ens3", "ens4"]);
+    assert_eq!(configs[1].addressing, Addressing::Static);
+    assert_eq!(configs[1].gateway.as_deref(), Some("192.168.1.1"));
+    assert_eq!(configs[1].mac_address.as_deref(), Some("52:54:00:ab:cd:ef"));
+    assert_eq!(configs[2].addressing, Addressing::Dhcp);
+}
+
+#[test]
+fn test_load_follows_sourced_files() {
+    let root = crate::tests::fixture_root("ifupdown-source", &[
+        (INTERFACES, "auto lo\niface lo inet loopback\nsource-directory interfaces.d\n"),
+        ("/etc/network/interfaces.d/eth0", "auto eth0\niface eth0 inet dhcp\n"),
+        ("/etc/network/interfaces.d/eth0.bak", "iface eth0 inet static\n"),
+    ]);
+
+    let configs = load(&root).unwrap();
+
+    assert_eq!(configs.len(), 2);
+    assert_eq!(configs[1].path, "/etc/network/interfaces.d/eth0");
+}
+
+#[test]
+fn test_hwaddress_is_removed_from_the_stanza_only() {
+    let content = "iface ens3 inet dhcp\n    hwaddress ether 52:54:00:ab:cd:ef\n\niface ens4 inet dhcp\n    hwaddress ether 52:54:00:ab:cd:f0\n";
+
+    assert_eq!(
+        edit_stanza(content, "ens3", None, &["hwaddress"]),
+        "iface ens3 inet dhcp\n\niface ens4 inet dhcp\n    hwaddress ether 52:54:00:ab:cd:f0\n"
+    );
+}
+
+#[test]
+fn test_stanza_to_dhcp() {
+    let content = "auto ens3\niface ens3 inet static\n    address 192.168.1.10/24\n    gateway 192.168.1.1\n    dns-nameservers 192.168.1.2\n    mtu 1500\n\niface ens4 inet static\n    address 10.0.0.5/24\n";
+
+    assert_eq!(
+        stanza_to_dhcp(content, "ens3"),
+        "auto ens3\niface ens3 inet dhcp\n    mtu 1500\n\niface ens4 inet static\n    address 10.0.0.5/24\n"
+    );
+}
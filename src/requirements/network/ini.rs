@@ -0,0 +1,185 @@
+/// An entry of an INI-style file.
+#[derive(Debug, Clone)]
+struct Entry {
+    /// The index of the section occurrence, in `IniFile::sections`.
+    section: usize,
+    key: String,
+    value: String,
+    line: usize,
+}
+
+/// A section header, with the range of lines of the section including the header.
+#[derive(Debug, Clone)]
+struct Section {
+    name: String,
+    lines: std::ops::Range<usize>,
+}
+
+/// An INI-style file, as NetworkManager keyfiles and systemd-networkd units are.
+/// Sections may repeat, e.g. several `[Address]` sections, and so may keys.
+///
+/// Edits only touch the lines of the edited entries, so comments and the layout are kept.
+#[derive(Debug, Clone)]
+pub struct IniFile {
+    lines: Vec<String>,
+    sections: Vec<Section>,
+    entries: Vec<Entry>,
+}
+
+impl IniFile {
+    pub fn parse(content: &str) -> Self {
+        let lines: Vec<String> = content.lines().map(str::to_string).collect();
+        let mut sections: Vec<Section> = vec![Section { name: String::new(), lines: 0..0 }];
+        let mut entries = vec![];
+
+        for (index, line) in lines.iter().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                sections.push(Section { name: name.trim().to_string(), lines: index..index });
+            } else if let Some((key, value)) = line.split_once('=') {
+                let section = sections.len() - 1;
+                entries.push(Entry { section, key: key.trim().to_string(), value: value.trim().to_string(), line: index });
+            }
+        }
+
+        // a section ends where the next one starts
+        let starts: Vec<usize> = sections.iter().skip(1).map(|section| section.lines.start).chain([lines.len()]).collect();
+        for (section, end) in sections.iter_mut().zip(starts) {
+            section.lines.end = end;
+        }
+
+        IniFile { lines, sections, entries }
+    }
+
+    pub fn content(&self) -> String {
+        let end = self.lines.iter().rposition(|line| !line.trim().is_empty()).map_or(0, |last| last + 1);
+        let mut content = self.lines[..end].join("\n");
+        content.push('\n');
+        content
+    }
+
+    /// The last value of the key in the sections with this name.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.get_all(section, key).pop()
+    }
+
+    /// Every value of the key in the sections with this name, in order.
+    pub fn get_all(&self, section: &str, key: &str) -> Vec<&str> {
+        self.entries_of(section)
+            .into_iter()
+            .filter(|entry| entry.key == key)
+            .map(|entry| entry.value.as_str())
+            .collect()
+    }
+
+    /// The entries of the sections with this name whose key starts with the prefix,
+    /// e.g. `address1`, `address2` for the prefix `address`.
+    pub fn get_numbered(&self, section: &str, prefix: &str) -> Vec<&str> {
+        self.entries_of(section)
+            .into_iter()
+            .filter(|entry| is_numbered(&entry.key, prefix))
+            .map(|entry| entry.value.as_str())
+            .collect()
+    }
+
+    /// Replaces the value of the last entry of the key, or adds the entry at the end of the
+    /// last section with this name, or in a new section at the end of the file.
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        let line = format!("{}={}", key, value);
+        let last_entry = self.entries_of(section).into_iter().rfind(|entry| entry.key == key).map(|entry| entry.line);
+        if let Some(index) = last_entry {
+            self.lines[index] = line;
+        } else if let Some(existing) = self.sections.iter().skip(1).rfind(|existing| existing.name == section) {
+            // after the last entry, before the blank lines separating the next section
+            let mut end = existing.lines.end;
+            while end > existing.lines.start + 1 && self.lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+            self.lines.insert(end, line);
+        } else {
+            if self.lines.last().is_some_and(|last| !last.trim().is_empty()) {
+                self.lines.push(String::new());
+            }
+            self.lines.push(format!("[{}]", section));
+            self.lines.push(line);
+        }
+        self.reparse();
+    }
+
+    /// Removes the entries of the sections with this name whose key matches.
+    pub fn remove(&mut self, section: &str, matches: impl Fn(&str) -> bool) {
+        let lines: Vec<usize> = self.entries_of(section).into_iter().filter(|entry| matches(&entry.key)).map(|entry| entry.line).collect();
+        self.remove_lines(lines);
+    }
+
+    /// Removes the entries numbered after the prefix, e.g. `address1` for the prefix `address`.
+    pub fn remove_numbered(&mut self, section: &str, prefix: &str) {
+        self.remove(section, |key| is_numbered(key, prefix));
+    }
+
+    /// Removes whole sections with this name, for which the predicate on their entries holds.
+    pub fn remove_sections(&mut self, section: &str, matches: impl Fn(&[(&str, &str)]) -> bool) {
+        let mut lines = vec![];
+        for (index, existing) in self.sections.iter().enumerate().skip(1).filter(|(_, existing)| existing.name == section) {
+            let entries: Vec<(&str, &str)> = self
+                .entries
+                .iter()
+                .filter(|entry| entry.section == index)
+                .map(|entry| (entry.key.as_str(), entry.value.as_str()))
+                .collect();
+            if matches(&entries) {
+                lines.extend(existing.lines.clone());
+            }
+        }
+        self.remove_lines(lines);
+    }
+
+    fn entries_of(&self, section: &str) -> Vec<&Entry> {
+        self.entries.iter().filter(|entry| self.sections[entry.section].name == section).collect()
+    }
+
+    fn remove_lines(&mut self, lines: Vec<usize>) {
+        if lines.is_empty() {
+            return;
+        }
+        self.lines = std::mem::take(&mut self.lines)
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !lines.contains(index))
+            .map(|(_, line)| line)
+            .collect();
+        self.reparse();
+    }
+
+    fn reparse(&mut self) {
+        *self = IniFile::parse(&self.lines.join("\n"));
+    }
+}
+
+fn is_numbered(key: &str, prefix: &str) -> bool {
+    key.strip_prefix(prefix).is_some_and(|number| number.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[test]
+fn test_parse_repeated_sections() {
+    let ini = IniFile::parse("[Match]\nName=ens3\n\n# comment\n[Address]\nAddress=10.0.0.5/24\n[Address]\nAddress = 10.0.1.5/24\n");
+
+    assert_eq!(ini.get("Match", "Name"), Some("ens3"));
+    assert_eq!(ini.get_all("Address", "Address"), vec!["10.0.0.5/24", "10.0.1.5/24"]);
+    assert_eq!(ini.get("Network", "DHCP"), None);
+}
+
+#[test]
+fn test_edits_keep_the_layout() {
+    let mut ini = IniFile::parse("[Match]\nName=ens3\n\n[Network]\n# static\nAddress=10.0.0.5/24\nGateway=10.0.0.1\n\n[Route]\nGateway=10.0.0.1\n");
+
+    ini.remove("Network", |key| key == "Address" || key == "Gateway");
+    ini.set("Network", "DHCP", "ipv4");
+    ini.remove_sections("Route", |entries| entries.iter().any(|(key, _)| *key == "Gateway"));
+    ini.set("DHCPv4", "UseDNS", "yes");
+
+    assert_eq!(ini.content(), "[Match]\nName=ens3\n\n[Network]\n# static\nDHCP=ipv4\n\n[DHCPv4]\nUseDNS=yes\n");
+}
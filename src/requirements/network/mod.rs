@@ -0,0 +1,263 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+
+use serde::Serialize;
+
+use crate::requirements::{FixContext, SystemRoot};
+use crate::utils::FileError;
+
+pub mod ifcfg;
+pub mod ifupdown;
+pub mod ini;
+pub mod netplan;
+pub mod network_manager;
+pub mod networkd;
+pub mod udev;
+
+/// The network configuration tooling an interface configuration belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// `/etc/netplan/*.yaml` (Ubuntu).
+    Netplan,
+    /// Keyfiles in `/etc/NetworkManager/system-connections` (RHEL 9, Rocky 9).
+    NetworkManager,
+    /// `ifcfg-*` files in `/etc/sysconfig/network-scripts` (RHEL 7 and 8, Rocky 8).
+    Ifcfg,
+    /// `/etc/network/interfaces` (Debian).
+    Ifupdown,
+    /// `.network` units in `/etc/systemd/network`.
+    Networkd,
+    /// `ifcfg-*` files in `/etc/sysconfig/network` (SUSE).
+    Wicked,
+}
+
+/// How an interface gets its IPv4 address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Addressing {
+    Dhcp,
+    Static,
+    /// No IPv4 address, e.g. `method=disabled` or `BOOTPROTO=none` without `IPADDR`.
+    Unconfigured,
+}
+
+impl Display for Addressing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Addressing::Dhcp => write!(f, "dhcp"),
+            Addressing::Static => write!(f, "static"),
+            Addressing::Unconfigured => write!(f, "unconfigured"),
+        }
+    }
+}
+
+/// The configuration of one interface, as found in the files of a backend.
+#[derive(Debug, Clone, Serialize)]
+pub struct InterfaceConfig {
+    pub backend: Backend,
+    /// The file the configuration comes from.
+    pub path: String,
+    /// The identifier of the configuration in its file, e.g. the netplan key or the connection id.
+    pub id: String,
+    /// The interface name the configuration applies to, possibly a glob such as `en*`. None means any interface.
+    pub name: Option<String>,
+    /// The MAC address the configuration is bound to, in lowercase.
+    pub mac_address: Option<String>,
+    pub addressing: Addressing,
+    pub addresses: Vec<String>,
+    pub gateway: Option<String>,
+    pub dns: Vec<String>,
+}
+
+impl InterfaceConfig {
+    fn new(backend: Backend, path: &str, id: &str, addressing: Addressing) -> Self {
+        InterfaceConfig {
+            backend,
+            path: path.to_string(),
+            id: id.to_string(),
+            name: None,
+            mac_address: None,
+            addressing,
+            addresses: vec![],
+            gateway: None,
+            dns: vec![],
+        }
+    }
+
+    /// True when the configuration applies to the interface.
+    pub fn applies_to(&self, interface: &Interface) -> bool {
+        let name_matches = self.name.as_ref().is_none_or(|pattern| {
+            pattern.split_whitespace().any(|pattern| glob_match(pattern, &interface.name))
+        });
+        let mac_matches = match (&self.mac_address, &interface.mac_address) {
+            (Some(configured), Some(actual)) => configured == actual,
+            _ => true,
+        };
+        name_matches && mac_matches
+    }
+
+    fn is_loopback(&self) -> bool {
+        self.name.as_deref() == Some("lo")
+    }
+}
+
+/// A network interface of the running system.
+#[derive(Debug, Clone, Serialize)]
+pub struct Interface {
+    pub name: String,
+    pub mac_address: Option<String>,
+}
+
+/// The interface configurations of every backend found in the target system, loopback excluded.
+pub fn inspect(root: &SystemRoot) -> Result<Vec<InterfaceConfig>, Box<dyn Error>> {
+    let mut configs = vec![];
+    configs.extend(netplan::load(root)?);
+    configs.extend(network_manager::load(root)?);
+    configs.extend(ifcfg::load(root)?);
+    configs.extend(ifupdown::load(root)?);
+    configs.extend(networkd::load(root)?);
+    configs.retain(|config| !config.is_loopback());
+    Ok(configs)
+}
+
+/// Rewrites a configuration to get the IPv4 address through DHCP, in the format of its backend,
+/// removing the static addresses, the gateway and the DNS servers.
+pub fn convert_to_dhcp(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    match config.backend {
+        Backend::Netplan => netplan::to_dhcp(ctx, config),
+        Backend::NetworkManager => network_manager::to_dhcp(ctx, config),
+        Backend::Ifcfg | Backend::Wicked => ifcfg::to_dhcp(ctx, config),
+        Backend::Ifupdown => ifupdown::to_dhcp(ctx, config),
+        Backend::Networkd => networkd::to_dhcp(ctx, config),
+    }
+}
+
+/// Removes the MAC address a configuration is bound to, in the format of its backend.
+pub fn remove_mac_binding(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    match config.backend {
+        Backend::Netplan => netplan::remove_mac_binding(ctx, config),
+        Backend::NetworkManager => network_manager::remove_mac_binding(ctx, config),
+        Backend::Ifcfg | Backend::Wicked => ifcfg::remove_mac_binding(ctx, config),
+        Backend::Ifupdown => ifupdown::remove_mac_binding(ctx, config),
+        Backend::Networkd => networkd::remove_mac_binding(ctx, config),
+    }
+}
+
+/// The MAC addresses of the network interfaces of the running system, in lowercase.
+pub fn host_mac_addresses() -> Vec<String> {
+    fs::read_dir("/sys/class/net")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| fs::read_to_string(entry.path().join("address")).ok())
+                .map(|address| normalize_mac(&address))
+                .filter(|address| address != "00:00:00:00:00:00")
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Finds the primary interface. On the host it is the interface of the default route.
+/// An alternate root has no running interfaces, so the configuration with a gateway is
+/// used, or else the first one using DHCP, or else the first one.
+pub fn primary_interface(root: &SystemRoot, configs: &[InterfaceConfig]) -> Option<Interface> {
+    if root.is_host() {
+        let name = default_route_interface(&fs::read_to_string("/proc/net/route").ok()?)?;
+        let mac_address = fs::read_to_string(format!("/sys/class/net/{}/address", name))
+            .ok()
+            .map(|address| address.trim().to_lowercase());
+        return Some(Interface { name, mac_address });
+    }
+
+    let config = configs
+        .iter()
+        .find(|config| config.gateway.is_some())
+        .or_else(|| configs.iter().find(|config| config.addressing == Addressing::Dhcp))
+        .or_else(|| configs.first())?;
+    let name = match &config.name {
+        Some(name) if !name.contains(['*', '?', '[', ' ']) => name.clone(),
+        _ => config.id.clone(),
+    };
+    Some(Interface { name, mac_address: config.mac_address.clone() })
+}
+
+/// The interface of the IPv4 default route with the lowest metric, from `/proc/net/route`.
+fn default_route_interface(route_table: &str) -> Option<String> {
+    route_table
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>())
+        .filter(|fields| fields.len() > 6 && fields[1] == "00000000")
+        .min_by_key(|fields| fields[6].parse::<u32>().unwrap_or(u32::MAX))
+        .map(|fields| fields[0].to_string())
+}
+
+/// Lists the files of a directory of the target system, sorted by name, or none if it does not exist.
+fn config_files(root: &SystemRoot, dir: &str, accept: impl Fn(&str) -> bool) -> Result<Vec<String>, FileError> {
+    if !root.path(dir).is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut files = vec![];
+    for entry in fs::read_dir(root.path(dir)).map_err(|e| FileError::new("read directory", dir, e))? {
+        let entry = entry.map_err(|e| FileError::new("read directory", dir, e))?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_file() && accept(&file_name) {
+            files.push(format!("{}/{}", dir, file_name));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Matches a name against a shell glob with `*`, `?` and `[...]`, as interface names are in
+/// netplan `match` and networkd `Name=`.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    glob_match_from(&pattern, &name)
+}
+
+fn glob_match_from(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| glob_match_from(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && glob_match_from(&pattern[1..], &name[1..]),
+        Some('[') => match (pattern.iter().position(|&c| c == ']'), name.first()) {
+            (Some(end), Some(c)) if end > 1 => {
+                pattern[1..end].contains(c) && glob_match_from(&pattern[end + 1..], &name[1..])
+            }
+            _ => false,
+        },
+        Some(c) => name.first() == Some(c) && glob_match_from(&pattern[1..], &name[1..]),
+    }
+}
+
+fn normalize_mac(mac: &str) -> String {
+    mac.trim().to_lowercase()
+}
+
+#[test]
+fn test_default_route_interface() {
+    let route_table = "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\n\
+        ens4\t00000000\t0100000A\t0003\t0\t0\t200\t00000000\n\
+        ens3\t00000000\t0100A8C0\t0003\t0\t0\t100\t00000000\n\
+        ens3\t0000A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\n";
+
+    assert_eq!(default_route_interface(route_table).unwrap(), "ens3");
+}
+
+#[test]
+fn test_applies_to_name_globs_and_mac() {
+    let mut config = InterfaceConfig::new(Backend::Networkd, "/etc/systemd/network/10-en.network", "10-en", Addressing::Dhcp);
+    config.name = Some("eth0 en*".to_string());
+    let interface = Interface { name: "ens3".to_string(), mac_address: Some("02:00:00:aa:bb:cc".to_string()) };
+
+    assert!(config.applies_to(&interface));
+    config.mac_address = Some("52:54:00:12:34:56".to_string());
+    assert!(!config.applies_to(&interface));
+    assert!(glob_match("eth[01]", "eth1") && !glob_match("eth?", "eth10"));
+}
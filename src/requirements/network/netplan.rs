@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+
+use serde_yaml::{Mapping, Value};
+use thiserror::Error;
+
+use crate::requirements::network::{config_files, normalize_mac, Addressing, Backend, InterfaceConfig};
+use crate::requirements::{FixContext, SystemRoot};
+use crate::utils::FileError;
+
+/// The netplan directories, lowest precedence first: a file of `/run/netplan` shadows
+/// the file of the same name in `/etc/netplan`, which shadows the one in `/lib/netplan`.
+pub const NETPLAN_DIRS: [&str; 3] = ["/lib/netplan", ETC_NETPLAN, "/run/netplan"];
+/// The only netplan directory the fixes write to.
+pub const ETC_NETPLAN: &str = "/etc/netplan";
+
+#[derive(Error, Debug)]
+pub enum NetplanError {
+    #[error("Failed to parse {path}")]
+    Parse { path: String, source: serde_yaml::Error },
+}
+
+/// The device types whose definitions can carry addresses.
+const DEVICE_TYPES: [&str; 4] = ["ethernets", "bonds", "bridges", "vlans"];
+
+/// Loads the device definitions of the netplan files. Like netplan, the files are read in the
+/// order of their names, and the definitions of a device in later files are merged over the
+/// earlier ones.
+pub fn load(root: &SystemRoot) -> Result<Vec<InterfaceConfig>, Box<dyn Error>> {
+    let mut devices: Vec<(String, String, Value)> = vec![];
+    for path in netplan_files(root)? {
+        let document = read_document(root, &path)?;
+
+        for device_type in DEVICE_TYPES {
+            let definitions = match document.get("network").and_then(|network| network.get(device_type)) {
+                Some(Value::Mapping(definitions)) => definitions,
+                _ => continue,
+            };
+            for (id, definition) in definitions {
+                let id = match id.as_str() {
+                    Some(id) => id.to_string(),
+                    None => continue,
+                };
+                match devices.iter_mut().find(|(existing, _, _)| *existing == id) {
+                    Some((_, last_path, merged)) => {
+                        merge(merged, definition);
+                        last_path.clone_from(&path);
+                    }
+                    None => devices.push((id, path.clone(), definition.clone())),
+                }
+            }
+        }
+    }
+
+    Ok(devices.iter().map(|(id, path, definition)| parse_device(path, id, definition)).collect())
+}
+
+/// The netplan files in the order netplan reads them, the shadowed ones left out.
+fn netplan_files(root: &SystemRoot) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut files = BTreeMap::new();
+    for dir in NETPLAN_DIRS {
+        for path in config_files(root, dir, |name| name.ends_with(".yaml"))? {
+            let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+            files.insert(name, path);
+        }
+    }
+    Ok(files.into_values().collect())
+}
+
+fn read_document(root: &SystemRoot, path: &str) -> Result<Value, Box<dyn Error>> {
+    let content = root.read_to_string(path)?;
+    Ok(serde_yaml::from_str(&content).map_err(|source| NetplanError::Parse { path: path.to_string(), source })?)
+}
+
+/// Enables `dhcp4` for the device in every netplan file defining it, and drops its static addresses,
+/// default routes and DNS servers.
+pub fn to_dhcp(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    edit_definitions(ctx, &config.id, definition_to_dhcp)
+}
+
+/// Drops the `macaddress` of the `match` of the device. When nothing else is matched, the device
+/// matches the kernel names of ethernet interfaces instead, since a device without `match` only
+/// applies to an interface named like its id. netplan matches the name from before `set-name`, which
+/// is kept, and takes a single glob, so `e*` stands for `en*` and `eth*`.
+pub fn remove_mac_binding(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    edit_definitions(ctx, &config.id, |definition| {
+        if let Some(Value::Mapping(matching)) = definition.get_mut("match") {
+            matching.remove("macaddress");
+            if matching.is_empty() {
+                matching.insert(Value::from("name"), Value::from("e*"));
+            }
+        }
+    })
+}
+
+/// Edits the definition of a device in every netplan file defining it. The files are written back
+/// from the parsed YAML, so comments are lost, the journal keeps the original files.
+///
+/// Only `/etc/netplan` is written: the files of `/lib/netplan` belong to packages and the ones of
+/// `/run/netplan` are lost on reboot, so their edits go to a file of the same name in `/etc/netplan`,
+/// which shadows the one of `/lib`, and replaces the one of `/run` from the next boot on.
+fn edit_definitions(ctx: &mut FixContext, id: &str, edit: impl Fn(&mut Mapping)) -> Result<(), Box<dyn Error>> {
+    for path in netplan_files(ctx.root())? {
+        let mut document = read_document(ctx.root(), &path)?;
+        let mut defined = false;
+
+        for device_type in DEVICE_TYPES {
+            let definition = match document
+                .get_mut("network")
+                .and_then(|network| network.get_mut(device_type))
+                .and_then(|definitions| definitions.get_mut(id))
+            {
+                Some(Value::Mapping(definition)) => definition,
+                _ => continue,
+            };
+            edit(definition);
+            defined = true;
+        }
+
+        if defined {
+            let name = path.rsplit('/').next().unwrap_or(&path);
+            let target = format!("{}/{}", ETC_NETPLAN, name);
+            let dir = ctx.root().path(ETC_NETPLAN);
+            if !ctx.is_dry_run() && !dir.is_dir() {
+                fs::create_dir_all(&dir).map_err(|e| FileError::new("create directory", &dir.to_string_lossy(), e))?;
+            }
+            ctx.write_file(&target, &serde_yaml::to_string(&document)?)?;
+        }
+    }
+    Ok(())
+}
+
+fn definition_to_dhcp(definition: &mut Mapping) {
+    definition.insert(Value::from("dhcp4"), Value::Bool(true));
+    for key in ["addresses", "gateway4", "nameservers"] {
+        definition.remove(key);
+    }
+
+    if let Some(Value::Sequence(routes)) = definition.get_mut("routes") {
+        routes.retain(|route| !matches!(route.get("to").and_then(Value::as_str), Some("default" | "0.0.0.0/0")));
+        if routes.is_empty() {
+            definition.remove("routes");
+        }
+    }
+    if let Some(Value::Mapping(overrides)) = definition.get_mut("dhcp4-overrides") {
+        for key in ["use-dns", "use-routes"] {
+            overrides.remove(key);
+        }
+    }
+}
+
+/// Merges a later definition over an earlier one: mappings are merged key by key, other values replaced.
+fn merge(base: &mut Value, over: &Value) {
+    match (base, over) {
+        (Value::Mapping(base), Value::Mapping(over)) => {
+            for (key, value) in over {
+                match base.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over.clone(),
+    }
+}
+
+fn parse_device(path: &str, id: &str, definition: &Value) -> InterfaceConfig {
+    let empty = Mapping::new();
+    let definition = definition.as_mapping().unwrap_or(&empty);
+    let get = |key: &str| definition.get(key);
+    let matching = get("match");
+
+    let addresses: Vec<String> = match get("addresses") {
+        // an address is either `10.0.0.5/24`, or a mapping from the address to its options
+        Some(Value::Sequence(addresses)) => addresses
+            .iter()
+            .filter_map(|address| match address {
+                Value::Mapping(address) => address.keys().next().and_then(Value::as_str),
+                address => address.as_str(),
+            })
+            .map(str::to_string)
+            .collect(),
+        _ => vec![],
+    };
+    let addressing = if get("dhcp4").is_some_and(is_true) {
+        Addressing::Dhcp
+    } else if !addresses.is_empty() {
+        Addressing::Static
+    } else {
+        Addressing::Unconfigured
+    };
+
+    let mut config = InterfaceConfig::new(Backend::Netplan, path, id, addressing);
+    config.name = get("set-name")
+        .or_else(|| matching.and_then(|matching| matching.get("name")))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    if matching.is_none() && config.name.is_none() {
+        config.name = Some(id.to_string());
+    }
+    config.mac_address = matching
+        .and_then(|matching| matching.get("macaddress"))
+        .and_then(Value::as_str)
+        .map(normalize_mac);
+    config.addresses = addresses;
+    config.gateway = get("gateway4").and_then(Value::as_str).map(str::to_string).or_else(|| default_route(get("routes")));
+    config.dns = get("nameservers")
+        .and_then(|nameservers| nameservers.get("addresses"))
+        .and_then(Value::as_sequence)
+        .map(|addresses| addresses.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default();
+    config
+}
+
+/// The gateway of the IPv4 default route in the `routes` of a device.
+fn default_route(routes: Option<&Value>) -> Option<String> {
+    routes?
+        .as_sequence()?
+        .iter()
+        .filter(|route| matches!(route.get("to").and_then(Value::as_str), Some("default" | "0.0.0.0/0")))
+        .filter_map(|route| route.get("via").and_then(Value::as_str))
+        .find(|via| !via.contains(':'))
+        .map(str::to_string)
+}
+
+/// netplan accepts YAML 1.1 booleans, which YAML 1.2 parsers read as strings.
+fn is_true(value: &Value) -> bool {
+    match value {
+        Value::Bool(value) => *value,
+        Value::String(value) => matches!(value.to_lowercase().as_str(), "true" | "yes" | "on" | "y"),
+        _ => false,
+    }
+}
+
+#[test]
+fn test_load_merges_definitions_of_later_files() {
+    let root = crate::tests::fixture_root("netplan-merge", &[
+        ("/etc/netplan/50-cloud-init.yaml", "network:\n  version: 2\n  ethernets:\n    eth0:\n      match:\n        macaddress: 52:54:00:AB:CD:EF\n      set-name: eth0\n      addresses: [10.0.0.5/24]\n      routes:\n        - to: default\n          via: 10.0.0.1\n"),
+        ("/etc/netplan/90-dhcp.yaml", "network:\n  ethernets:\n    eth0:\n      dhcp4: yes\n"),
+        ("/lib/netplan/90-dhcp.yaml", "network:\n  ethernets:\n    eth0:\n      dhcp4: false\n"),
+    ]);
+
+    let configs = load(&root).unwrap();
+
+    assert_eq!(configs.len(), 1);
+    assert_eq!(configs[0].path, "/etc/netplan/90-dhcp.yaml");
+    assert_eq!(configs[0].addressing, Addressing::Dhcp);
+    assert_eq!(configs[0].name.as_deref(), Some("eth0"));
+    assert_eq!(configs[0].mac_address.as_deref(), Some("52:54:00:ab:cd:ef"));
+    assert_eq!(configs[0].gateway.as_deref(), Some("10.0.0.1"));
+}
+
+#[test]
+fn test_device_to_dhcp() {
+    let path = "/etc/netplan/50-cloud-init.yaml";
+    let root = crate::tests::fixture_root("netplan-to-dhcp", &[
+        (path, "network:\n  version: 2\n  ethernets:\n    eth0:\n      addresses: [10.0.0.5/24]\n      nameservers:\n        addresses: [10.0.0.2]\n      routes:\n        - to: default\n          via: 10.0.0.1\n        - to: 10.1.0.0/16\n          via: 10.0.0.254\n      mtu: 9000\n"),
+    ]);
+    let config = load(&root).unwrap().remove(0);
+
+    to_dhcp(&mut FixContext::new(&root, false), &config).unwrap();
+
+    let config = load(&root).unwrap().remove(0);
+    assert_eq!(config.addressing, Addressing::Dhcp);
+    assert!(config.addresses.is_empty() && config.dns.is_empty() && config.gateway.is_none());
+    assert!(root.read_to_string(path).unwrap().contains("mtu: 9000"));
+    assert!(root.read_to_string(path).unwrap().contains("via: 10.0.0.254"));
+}
+
+#[test]
+fn test_edits_of_lib_files_go_to_etc() {
+    let lib = "/lib/netplan/50-default.yaml";
+    let root = crate::tests::fixture_root("netplan-lib", &[
+        (lib, "network:\n  ethernets:\n    eth0:\n      addresses: [10.0.0.5/24]\n"),
+    ]);
+    let config = load(&root).unwrap().remove(0);
+
+    to_dhcp(&mut FixContext::new(&root, false), &config).unwrap();
+
+    assert_eq!(root.read_to_string(lib).unwrap(), "network:\n  ethernets:\n    eth0:\n      addresses: [10.0.0.5/24]\n");
+    let config = load(&root).unwrap().remove(0);
+    assert_eq!(config.path, "/etc/netplan/50-default.yaml");
+    assert_eq!(config.addressing, Addressing::Dhcp);
+}
+
+#[test]
+fn test_remove_mac_binding() {
+    let path = "/etc/netplan/50-cloud-init.yaml";
+    let root = crate::tests::fixture_root("netplan-mac", &[
+        (path, "network:\n  ethernets:\n    eth0:\n      match:\n        macaddress: 52:54:00:ab:cd:ef\n      set-name: eth0\n      dhcp4: true\n    id1:\n      match:\n        macaddress: 52:54:00:ab:cd:f0\n      dhcp4: true\n"),
+    ]);
+
+    for config in load(&root).unwrap() {
+        remove_mac_binding(&mut FixContext::new(&root, false), &config).unwrap();
+    }
+
+    assert_eq!(
+        root.read_to_string(path).unwrap(),
+        "network:\n  ethernets:\n    eth0:\n      match:\n        name: e*\n      set-name: eth0\n      dhcp4: true\n    id1:\n      match:\n        name: e*\n      dhcp4: true\n"
+    );
+    assert!(load(&root).unwrap().iter().all(|config| config.mac_address.is_none()));
+}
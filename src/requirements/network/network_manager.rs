@@ -0,0 +1,120 @@
+use std::error::Error;
+
+use crate::requirements::network::ini::IniFile;
+use crate::requirements::network::{config_files, normalize_mac, Addressing, Backend, InterfaceConfig};
+use crate::requirements::{FixContext, SystemRoot};
+
+pub const CONNECTIONS_DIR: &str = "/etc/NetworkManager/system-connections";
+
+/// Loads the wired connections of the NetworkManager keyfiles.
+pub fn load(root: &SystemRoot) -> Result<Vec<InterfaceConfig>, Box<dyn Error>> {
+    let mut configs = vec![];
+    for path in config_files(root, CONNECTIONS_DIR, |name| !name.ends_with('~') && !name.starts_with('.'))? {
+        if let Some(config) = parse(&path, &root.read_to_string(&path)?) {
+            configs.push(config);
+        }
+    }
+    Ok(configs)
+}
+
+/// Parses a keyfile, skipping the connections that are not wired, such as bridges or Wi-Fi.
+pub fn parse(path: &str, content: &str) -> Option<InterfaceConfig> {
+    let keyfile = IniFile::parse(content);
+    if !matches!(keyfile.get("connection", "type"), Some("ethernet" | "802-3-ethernet")) {
+        return None;
+    }
+
+    // the address entries are `address1=10.0.0.5/24,10.0.0.1`, with an optional gateway
+    let entries: Vec<(&str, Option<&str>)> = keyfile
+        .get_numbered("ipv4", "address")
+        .into_iter()
+        .chain(keyfile.get_numbered("ipv4", "addresses"))
+        .flat_map(|value| value.split(';').filter(|entry| !entry.is_empty()))
+        .map(|entry| match entry.split_once(',') {
+            Some((address, gateway)) => (address.trim(), Some(gateway.trim())),
+            None => (entry.trim(), None),
+        })
+        .collect();
+
+    let addressing = match keyfile.get("ipv4", "method") {
+        Some("auto") | None => Addressing::Dhcp,
+        Some("manual") => Addressing::Static,
+        Some(_) => Addressing::Unconfigured,
+    };
+    let id = keyfile.get("connection", "id").map(str::to_string).unwrap_or_else(|| {
+        path.rsplit('/').next().unwrap_or(path).trim_end_matches(".nmconnection").to_string()
+    });
+
+    let mut config = InterfaceConfig::new(Backend::NetworkManager, path, &id, addressing);
+    config.name = keyfile.get("connection", "interface-name").map(str::to_string);
+    config.mac_address = keyfile
+        .get("ethernet", "mac-address")
+        .or_else(|| keyfile.get("802-3-ethernet", "mac-address"))
+        .map(normalize_mac);
+    config.addresses = entries.iter().map(|(address, _)| address.to_string()).collect();
+    config.gateway = keyfile
+        .get("ipv4", "gateway")
+        .or_else(|| entries.iter().find_map(|(_, gateway)| *gateway))
+        .map(str::to_string);
+    config.dns = keyfile
+        .get("ipv4", "dns")
+        .map(|dns| dns.split([';', ',']).map(str::trim).filter(|dns| !dns.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+    Some(config)
+}
+
+/// Switches the IPv4 method of the connection to DHCP, and drops its static addresses, gateway and DNS servers.
+pub fn to_dhcp(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    let mut keyfile = IniFile::parse(&ctx.root().read_to_string(&config.path)?);
+    keyfile.set("ipv4", "method", "auto");
+    keyfile.remove_numbered("ipv4", "address");
+    keyfile.remove_numbered("ipv4", "addresses");
+    keyfile.remove("ipv4", |key| {
+        matches!(key, "gateway" | "dns" | "dns-search" | "ignore-auto-dns" | "ignore-auto-routes" | "never-default")
+    });
+    ctx.write_file(&config.path, &keyfile.content())
+}
+
+/// Drops the `mac-address` the connection is restricted to.
+pub fn remove_mac_binding(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    let mut keyfile = IniFile::parse(&ctx.root().read_to_string(&config.path)?);
+    for section in ["ethernet", "802-3-ethernet"] {
+        keyfile.remove(section, |key| key == "mac-address");
+    }
+    ctx.write_file(&config.path, &keyfile.content())
+}
+
+#[test]
+fn test_parse_static_keyfile() {
+    let config = parse(
+        "/etc/NetworkManager/system-connections/ens3.nmconnection",
+        "[connection]\nid=System ens3\ntype=ethernet\ninterface-name=ens3\n\n[ethernet]\nmac-address=52:54:00:AB:CD:EF\n\n\
+         [ipv4]\naddress1=192.168.10.5/24,192.168.10.1\ndns=192.168.10.2;192.168.10.3;\nmethod=manual\n",
+    )
+    .unwrap();
+
+    assert_eq!(config.id, "System ens3");
+    assert_eq!(config.addressing, Addressing::Static);
+    assert_eq!(config.addresses, vec!["192.168.10.5/24"]);
+    assert_eq!(config.gateway.as_deref(), Some("192.168.10.1"));
+    assert_eq!(config.dns, vec!["192.168.10.2", "192.168.10.3"]);
+    assert_eq!(config.mac_address.as_deref(), Some("52:54:00:ab:cd:ef"));
+    assert!(parse("/etc/NetworkManager/system-connections/wifi.nmconnection", "[connection]\ntype=wifi\n").is_none());
+}
+
+#[test]
+fn test_keyfile_to_dhcp() {
+    use crate::requirements::fix::Change;
+
+    let path = "/etc/NetworkManager/system-connections/ens3.nmconnection";
+    let root = crate::tests::fixture_root("nm-to-dhcp", &[
+        (path, "[connection]\nid=ens3\ntype=ethernet\n\n[ipv4]\naddress1=192.168.10.5/24,192.168.10.1\ndns=192.168.10.2;\nmethod=manual\n\n[ipv6]\nmethod=auto\n"),
+    ]);
+    let config = load(&root).unwrap().remove(0);
+    let mut ctx = FixContext::new(&root, false);
+
+    to_dhcp(&mut ctx, &config).unwrap();
+
+    assert_eq!(root.read_to_string(path).unwrap(), "[connection]\nid=ens3\ntype=ethernet\n\n[ipv4]\nmethod=auto\n\n[ipv6]\nmethod=auto\n");
+    assert!(matches!(ctx.changes(), [Change::WriteFile { .. }]));
+}
@@ -0,0 +1,113 @@
+use std::error::Error;
+
+use crate::requirements::network::ini::IniFile;
+use crate::requirements::network::{config_files, normalize_mac, Addressing, Backend, InterfaceConfig};
+use crate::requirements::{FixContext, SystemRoot};
+
+/// Only the administrator's units are read: `/run/systemd/network` holds the units generated
+/// by netplan, which are inspected from the netplan files instead.
+pub const NETWORK_DIR: &str = "/etc/systemd/network";
+
+/// Loads the `.network` units of systemd-networkd, in the order networkd matches them.
+pub fn load(root: &SystemRoot) -> Result<Vec<InterfaceConfig>, Box<dyn Error>> {
+    let mut configs = vec![];
+    for path in config_files(root, NETWORK_DIR, |name| name.ends_with(".network"))? {
+        configs.push(parse(&path, &root.read_to_string(&path)?));
+    }
+    Ok(configs)
+}
+
+pub fn parse(path: &str, content: &str) -> InterfaceConfig {
+    let unit = IniFile::parse(content);
+
+    let addresses: Vec<String> = unit
+        .get_all("Network", "Address")
+        .into_iter()
+        .chain(unit.get_all("Address", "Address"))
+        .map(str::to_string)
+        .collect();
+    let addressing = match unit.get("Network", "DHCP").map(str::to_lowercase).as_deref() {
+        Some("yes" | "true" | "on" | "1" | "ipv4" | "both") => Addressing::Dhcp,
+        _ if !addresses.is_empty() => Addressing::Static,
+        _ => Addressing::Unconfigured,
+    };
+
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let mut config = InterfaceConfig::new(Backend::Networkd, path, file_name.trim_end_matches(".network"), addressing);
+    config.name = unit.get("Match", "Name").map(str::to_string);
+    config.mac_address = unit.get("Match", "MACAddress").map(normalize_mac);
+    config.addresses = addresses;
+    config.gateway = unit
+        .get("Network", "Gateway")
+        .or_else(|| unit.get("Route", "Gateway"))
+        .map(str::to_string);
+    config.dns = unit
+        .get_all("Network", "DNS")
+        .iter()
+        .flat_map(|dns| dns.split_whitespace())
+        .map(str::to_string)
+        .collect();
+    config
+}
+
+/// Enables DHCP for IPv4 in the unit, and drops its static addresses, default routes and DNS servers.
+pub fn to_dhcp(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    let mut unit = IniFile::parse(&ctx.root().read_to_string(&config.path)?);
+    let dhcp = match unit.get("Network", "DHCP").map(str::to_lowercase).as_deref() {
+        Some("ipv6") => "yes",
+        _ => "ipv4",
+    };
+    unit.set("Network", "DHCP", dhcp);
+    unit.remove("Network", |key| matches!(key, "Address" | "Gateway" | "DNS"));
+    unit.remove_sections("Address", |_| true);
+    unit.remove_sections("Route", |entries| {
+        let gateway = entries.iter().any(|(key, _)| *key == "Gateway");
+        let default = entries.iter().all(|(key, value)| *key != "Destination" || matches!(*value, "0.0.0.0/0" | "0.0.0.0"));
+        gateway && default
+    });
+    for section in ["DHCP", "DHCPv4"] {
+        unit.remove(section, |key| matches!(key, "UseDNS" | "UseGateway" | "UseRoutes"));
+    }
+    ctx.write_file(&config.path, &unit.content())
+}
+
+/// Drops the `MACAddress=` of the `[Match]` section. A unit matching nothing else would apply to every
+/// link, the loopback included, so it is restricted to the usual names of ethernet interfaces instead.
+pub fn remove_mac_binding(ctx: &mut FixContext, config: &InterfaceConfig) -> Result<(), Box<dyn Error>> {
+    let mut unit = IniFile::parse(&ctx.root().read_to_string(&config.path)?);
+    unit.remove("Match", |key| key == "MACAddress");
+    if unit.get_all("Match", "Name").is_empty() && unit.get_all("Match", "Type").is_empty() && unit.get_all("Match", "Driver").is_empty() {
+        unit.set("Match", "Name", "en* eth*");
+    }
+    ctx.write_file(&config.path, &unit.content())
+}
+
+#[test]
+fn test_parse_network_unit() {
+    let config = parse(
+        "/etc/systemd/network/10-ens3.network",
+        "[Match]\nName=ens3\n\n[Network]\nDNS=10.0.0.2 10.0.0.3\n\n[Address]\nAddress=10.0.0.5/24\n\n[Route]\nGateway=10.0.0.1\n",
+    );
+
+    assert_eq!(config.id, "10-ens3");
+    assert_eq!(config.addressing, Addressing::Static);
+    assert_eq!(config.gateway.as_deref(), Some("10.0.0.1"));
+    assert_eq!(config.dns, vec!["10.0.0.2", "10.0.0.3"]);
+    assert_eq!(parse("/etc/systemd/network/20-dhcp.network", "[Match]\nName=en*\n[Network]\nDHCP=ipv4\n").addressing, Addressing::Dhcp);
+}
+
+#[test]
+fn test_unit_to_dhcp() {
+    let path = "/etc/systemd/network/10-ens3.network";
+    let root = crate::tests::fixture_root("networkd-to-dhcp", &[
+        (path, "[Match]\nName=ens3\n\n[Network]\nAddress=10.0.0.5/24\nDNS=10.0.0.2\n\n[Route]\nDestination=10.1.0.0/16\nGateway=10.0.0.254\n\n[Route]\nGateway=10.0.0.1\n"),
+    ]);
+    let config = load(&root).unwrap().remove(0);
+
+    to_dhcp(&mut FixContext::new(&root, false), &config).unwrap();
+
+    assert_eq!(
+        root.read_to_string(path).unwrap(),
+        "[Match]\nName=ens3\n\n[Network]\nDHCP=ipv4\n\n[Route]\nDestination=10.1.0.0/16\nGateway=10.0.0.254\n"
+    );
+}
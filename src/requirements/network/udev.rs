@@ -0,0 +1,68 @@
+use std::error::Error;
+
+use crate::requirements::network::normalize_mac;
+use crate::requirements::{FixContext, SystemRoot};
+
+/// The rules the legacy persistent-net generator wrote, naming each NIC after its MAC address.
+pub const PERSISTENT_NET_RULES: &str = "/etc/udev/rules.d/70-persistent-net.rules";
+
+/// A udev rule naming an interface after the MAC address of its NIC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamingRule {
+    pub mac_address: String,
+    /// The `NAME` the rule gives to the interface, if any.
+    pub name: Option<String>,
+}
+
+pub fn load(root: &SystemRoot) -> Result<Vec<NamingRule>, Box<dyn Error>> {
+    if !root.path(PERSISTENT_NET_RULES).is_file() {
+        return Ok(vec![]);
+    }
+    Ok(parse(&root.read_to_string(PERSISTENT_NET_RULES)?))
+}
+
+/// Parses the rules that match `ATTR{address}` of network devices.
+pub fn parse(content: &str) -> Vec<NamingRule> {
+    content.lines().filter_map(naming_rule).collect()
+}
+
+fn naming_rule(line: &str) -> Option<NamingRule> {
+    let line = line.trim();
+    if line.starts_with('#') || !line.contains("SUBSYSTEM==\"net\"") {
+        return None;
+    }
+    Some(NamingRule {
+        mac_address: normalize_mac(&key_value(line, "ATTR{address}==")?),
+        name: key_value(line, "NAME=").or_else(|| key_value(line, "NAME:=")),
+    })
+}
+
+/// The double-quoted value following `key` in a rule, e.g. `"eth0"` after `NAME=`.
+fn key_value(line: &str, key: &str) -> Option<String> {
+    line.split(',')
+        .map(str::trim)
+        .find_map(|field| field.strip_prefix(key))
+        .map(|value| value.trim_matches('"').to_string())
+}
+
+/// Removes the naming rules, keeping the comments and any other rule.
+pub fn remove_naming_rules(ctx: &mut FixContext) -> Result<(), Box<dyn Error>> {
+    let content = ctx.root().read_to_string(PERSISTENT_NET_RULES)?;
+    let kept: String = content
+        .lines()
+        .filter(|line| naming_rule(line).is_none())
+        .map(|line| format!("{}\n", line))
+        .collect();
+    ctx.write_file(PERSISTENT_NET_RULES, &kept)
+}
+
+#[test]
+fn test_parse_persistent_net_rules() {
+    let rules = parse(
+        "# This file was automatically generated by the /lib/udev/write_net_rules\n\n\
+         # PCI device 0x1af4:0x1000 (virtio-pci)\n\
+         SUBSYSTEM==\"net\", ACTION==\"add\", DRIVERS==\"?*\", ATTR{address}==\"52:54:00:AB:CD:EF\", ATTR{type}==\"1\", KERNEL==\"eth*\", NAME=\"eth0\"\n",
+    );
+
+    assert_eq!(rules, vec![NamingRule { mac_address: "52:54:00:ab:cd:ef".to_string(), name: Some("eth0".to_string()) }]);
+}
@@ -0,0 +1,131 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::requirements::os_release::{OsRelease, Version, VersionRange};
+use crate::utils::FileError;
+
+/// The matrix built into the binary, used unless another one is given with `--os-matrix`.
+const EMBEDDED_MATRIX: &str = include_str!("os_matrix.json");
+
+#[derive(Error, Debug)]
+pub enum OsMatrixError {
+    #[error("Failed to parse the OS matrix {path}")]
+    Parse { path: String, source: serde_json::Error },
+}
+
+/// The operating systems supported by IBM Cloud VPC custom images.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsMatrix {
+    pub entries: Vec<OsMatrixEntry>,
+}
+
+/// A supported release of an operating system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OsMatrixEntry {
+    /// The `os-release` ID the entry applies to, matched against the `ID` of the system.
+    pub id: String,
+    /// Whether the entry also applies to the derivatives listing its id in their `ID_LIKE`.
+    #[serde(default)]
+    pub match_id_like: bool,
+    /// The `os-release` VARIANT_ID the entry is restricted to, e.g. `coreos` for Fedora CoreOS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    pub name: String,
+    /// The supported version ranges, every version when empty.
+    #[serde(default)]
+    pub versions: Vec<VersionRange>,
+    /// The end of support of the release, after which the image works but no longer gets updates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_of_support: Option<NaiveDate>,
+    /// Whether the image must bring its own license (BYOL).
+    #[serde(default)]
+    pub byol: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+impl OsMatrixEntry {
+    /// Whether the entry applies to the system through one of its ids. Only the `ID` matches unless
+    /// the entry opts in to `match_id_like`, since a derivative is not supported as its parent.
+    pub fn applies_to(&self, os: &OsRelease, id: &str) -> bool {
+        self.id == id
+            && (id == os.id || self.match_id_like)
+            && self.variant.as_ref().is_none_or(|variant| os.variant_id.as_ref() == Some(variant))
+    }
+
+    pub fn contains(&self, version: Option<&Version>) -> bool {
+        match version {
+            _ if self.versions.is_empty() => true,
+            Some(version) => self.versions.iter().any(|range| range.contains(version)),
+            None => false,
+        }
+    }
+
+    pub fn is_end_of_support(&self, today: NaiveDate) -> bool {
+        self.end_of_support.is_some_and(|end_of_support| end_of_support < today)
+    }
+}
+
+impl OsMatrix {
+    pub fn embedded() -> Result<Self, OsMatrixError> {
+        Self::parse("embedded OS matrix", EMBEDDED_MATRIX)
+    }
+
+    /// Loads a matrix file of the host, e.g. a newer one than the embedded matrix.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path).map_err(|e| FileError::new("read", &path.to_string_lossy(), e))?;
+        Ok(Self::parse(&path.to_string_lossy(), &content)?)
+    }
+
+    pub fn parse(path: &str, content: &str) -> Result<Self, OsMatrixError> {
+        serde_json::from_str(content).map_err(|source| OsMatrixError::Parse { path: path.to_string(), source })
+    }
+
+    /// The first of the `ID` and `ID_LIKE` of the system that has entries applying to it, the one deciding
+    /// its support. A derivative is not supported through the versions of its parent when it has entries of its own.
+    pub fn family<'a>(&self, os: &'a OsRelease) -> Option<&'a str> {
+        os.ids().find(|id| self.entries.iter().any(|entry| entry.applies_to(os, id)))
+    }
+
+    /// The entry of the family that contains the version of the system.
+    pub fn entry(&self, family: &str, os: &OsRelease) -> Option<&OsMatrixEntry> {
+        let version = os.version();
+        self.entries.iter().find(|entry| entry.applies_to(os, family) && entry.contains(version.as_ref()))
+    }
+}
+
+#[test]
+fn test_embedded_matrix_is_valid() {
+    let matrix = OsMatrix::embedded().unwrap();
+
+    assert!(matrix.entries.iter().any(|entry| entry.id == "ubuntu"));
+}
+
+#[test]
+fn test_matrix_entries_match_the_version() {
+    let matrix = OsMatrix::parse(
+        "matrix.json",
+        r#"{ "entries": [
+            { "id": "rhel", "name": "RHEL 8", "versions": ["8.4-8"], "end_of_support": "2029-05-31" },
+            { "id": "rhel", "name": "RHEL 9", "versions": ["9"], "byol": true },
+            { "id": "centos", "match_id_like": true, "name": "CentOS Stream 9", "versions": ["9"] }
+        ] }"#,
+    )
+    .unwrap();
+    let os = OsRelease::parse("/etc/os-release", "ID=almalinux\nID_LIKE=\"rhel centos fedora\"\nVERSION_ID=9.2\n").unwrap();
+
+    assert_eq!(matrix.family(&os), Some("centos"));
+    let os = OsRelease { id: "rhel".to_string(), id_like: vec!["fedora".to_string()], ..os };
+    assert_eq!(matrix.family(&os), Some("rhel"));
+    assert_eq!(matrix.entry("rhel", &os).map(|entry| entry.byol), Some(true));
+    let os = OsRelease { version_id: Some("8.2".to_string()), ..os };
+    assert_eq!(matrix.entry("rhel", &os), None);
+    assert_eq!(matrix.entries[0].versions[0].to_string(), "8.4-8");
+    assert!(matrix.entries[0].is_end_of_support(NaiveDate::from_ymd_opt(2030, 1, 1).unwrap()));
+    assert!(OsMatrix::parse("matrix.json", r#"{ "entries": [{ "id": "rhel", "name": "RHEL", "versions": ["9-8"] }] }"#).is_err());
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::requirements::SystemRoot;
+use crate::utils::shell_assignments::ShellFile;
+
+/// Where the distributions install `os-release`, `/etc` taking precedence over `/usr/lib`.
+pub const OS_RELEASE_PATHS: [&str; 2] = ["/etc/os-release", "/usr/lib/os-release"];
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OsReleaseError {
+    #[error("Invalid version {0}, expected <major>[.<minor>]")]
+    InvalidVersion(String),
+    #[error("Invalid version range {0}, expected <version> or <version>-<version>")]
+    InvalidRange(String),
+}
+
+/// The identification of the operating system, from `os-release`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OsRelease {
+    /// `ID`, e.g. `ubuntu`, `rhel` or `sles`.
+    pub id: String,
+    /// `ID_LIKE`, the distributions this one derives from, closest first.
+    pub id_like: Vec<String>,
+    /// `VERSION_ID`, e.g. `22.04` or `9.2`. Rolling distributions do not set it.
+    pub version_id: Option<String>,
+    /// `VARIANT_ID`, e.g. `coreos` for Fedora CoreOS.
+    pub variant_id: Option<String>,
+    pub pretty_name: Option<String>,
+}
+
+impl OsRelease {
+    /// Loads the `os-release` of the system, None when the system has none.
+    pub fn load(root: &SystemRoot) -> Result<Option<Self>, Box<dyn Error>> {
+        match OS_RELEASE_PATHS.iter().find(|path| root.path(path).is_file()) {
+            Some(path) => Ok(Some(Self::parse(path, &root.read_to_string(path)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Parses an `os-release` file, which scripts may source.
+    pub fn parse(path: &str, content: &str) -> Result<Self, Box<dyn Error>> {
+        let file = ShellFile::parse(path, content, &mut HashMap::new())?;
+        let get = |key: &str| file.get(key).map(str::trim).filter(|value| !value.is_empty()).map(str::to_string);
+
+        Ok(OsRelease {
+            // the specification defaults ID to "linux"
+            id: get("ID").unwrap_or_else(|| "linux".to_string()),
+            id_like: get("ID_LIKE").map(|ids| ids.split_whitespace().map(str::to_string).collect()).unwrap_or_default(),
+            version_id: get("VERSION_ID"),
+            variant_id: get("VARIANT_ID"),
+            pretty_name: get("PRETTY_NAME"),
+        })
+    }
+
+    /// The `ID` followed by the `ID_LIKE`, the order in which a system should be matched.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.id.as_str()).chain(self.id_like.iter().map(String::as_str))
+    }
+
+    /// The major and minor version of `VERSION_ID`, None when it is missing or not numeric.
+    pub fn version(&self) -> Option<Version> {
+        self.version_id.as_deref().and_then(|version| version.parse().ok())
+    }
+}
+
+/// The major and minor components of a version. Further components, e.g. the point release
+/// of Ubuntu `22.04.3`, do not matter for support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl FromStr for Version {
+    type Err = OsReleaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || OsReleaseError::InvalidVersion(s.to_string());
+        let mut components = s.trim().split('.');
+        let major = components.next().and_then(|major| major.parse().ok()).ok_or_else(invalid)?;
+        let minor = match components.next() {
+            Some(minor) => minor.parse().map_err(|_| invalid())?,
+            None => 0,
+        };
+        Ok(Version { major, minor })
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// An inclusive range of versions, written `8`, `8.5` or `8.4-9`. A bound without a minor
+/// version covers every minor version of its major version, so `8` is every 8.x release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct VersionRange {
+    pub min: Version,
+    pub max: Version,
+}
+
+impl VersionRange {
+    pub fn contains(&self, version: &Version) -> bool {
+        self.min <= *version && *version <= self.max
+    }
+}
+
+impl FromStr for VersionRange {
+    type Err = OsReleaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bound = |bound: &str, is_max: bool| -> Result<Version, OsReleaseError> {
+            let version: Version = bound.parse().map_err(|_| OsReleaseError::InvalidRange(s.to_string()))?;
+            if is_max && !bound.contains('.') {
+                return Ok(Version { minor: u32::MAX, ..version });
+            }
+            Ok(version)
+        };
+        let (min, max) = s.split_once('-').unwrap_or((s, s));
+        let range = VersionRange { min: bound(min, false)?, max: bound(max, true)? };
+        if range.min > range.max {
+            return Err(OsReleaseError::InvalidRange(s.to_string()));
+        }
+        Ok(range)
+    }
+}
+
+impl TryFrom<String> for VersionRange {
+    type Error = OsReleaseError;
+
+    fn try_from(range: String) -> Result<Self, Self::Error> {
+        range.parse()
+    }
+}
+
+impl From<VersionRange> for String {
+    fn from(range: VersionRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl Display for VersionRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bound = |version: &Version| if version.minor == u32::MAX { version.major.to_string() } else { version.to_string() };
+        if self.min == self.max || (self.min.major == self.max.major && self.min.minor == 0 && self.max.minor == u32::MAX) {
+            write!(f, "{}", bound(&self.max))
+        } else {
+            write!(f, "{}-{}", bound(&self.min), bound(&self.max))
+        }
+    }
+}
+
+#[test]
+fn test_parse_os_release() {
+    let os = OsRelease::parse(
+        "/etc/os-release",
+        "NAME=\"Rocky Linux\"\nVERSION=\"9.2 (Blue Onyx)\"\nID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\nVERSION_ID=\"9.2\"\nPRETTY_NAME=\"Rocky Linux 9.2 (Blue Onyx)\"\n",
+    )
+    .unwrap();
+
+    assert_eq!(os.ids().collect::<Vec<&str>>(), vec!["rocky", "rhel", "centos", "fedora"]);
+    assert_eq!(os.version(), Some(Version { major: 9, minor: 2 }));
+    assert_eq!(os.pretty_name.as_deref(), Some("Rocky Linux 9.2 (Blue Onyx)"));
+    assert_eq!(OsRelease::parse("/etc/os-release", "PRETTY_NAME=\"Linux\"\n").unwrap().version(), None);
+}
+
+#[test]
+fn test_version_ranges() {
+    let range = |range: &str| range.parse::<VersionRange>().unwrap();
+    let version = |version: &str| version.parse::<Version>().unwrap();
+
+    assert!(range("22.04").contains(&version("22.04.3")));
+    assert!(!range("22.04").contains(&version("22.10")));
+    assert!(range("8").contains(&version("8.8")));
+    assert!(!range("8").contains(&version("9.0")));
+    assert!(range("8.4-9").contains(&version("9.2")));
+    assert!(!range("8.4-9").contains(&version("8.3")));
+    assert_eq!("9-8".parse::<VersionRange>(), Err(OsReleaseError::InvalidRange("9-8".to_string())));
+}
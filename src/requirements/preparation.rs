@@ -0,0 +1,213 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::requirements::fix::journal::STATE_DIR;
+use crate::requirements::report::FixOutcome;
+use crate::requirements::SystemRoot;
+use crate::utils::FileError;
+
+pub const RESUME_UNIT: &str = "vpc-migration-tools-resume.service";
+
+const UNIT_DIR: &str = "/etc/systemd/system";
+/// Where the program is copied for the resume unit to run it once the system boots, since the path it runs
+/// from may be gone by then, e.g. a download in `/tmp` or a path of the host for an alternate root.
+const RESUME_PROGRAM: &str = "/var/lib/vpc-migration-tools/bin/vpc-migration-tools";
+
+/// The fixes applied to a system by previous runs, kept across reboots so a later run can
+/// confirm that the fixes waiting for a reboot took effect.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PreparationState {
+    pub applied: Vec<AppliedFix>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedFix {
+    pub requirement: String,
+    pub journal_id: Option<String>,
+    pub applied_at: DateTime<Utc>,
+    /// The boot the fix was applied in, to tell whether the system was rebooted since.
+    pub boot_id: Option<String>,
+    pub outcome: FixOutcome,
+    /// When a fix pending a reboot was confirmed, or found not to take effect.
+    pub confirmed_at: Option<DateTime<Utc>>,
+}
+
+impl AppliedFix {
+    pub fn is_pending(&self) -> bool {
+        self.outcome == FixOutcome::PendingReboot && self.confirmed_at.is_none()
+    }
+}
+
+impl PreparationState {
+    fn file() -> String {
+        format!("{}/state.json", STATE_DIR)
+    }
+
+    /// Loads the state of the target system, or an empty state if no fix was applied yet.
+    pub fn load(root: &SystemRoot) -> Result<Self, Box<dyn Error>> {
+        if !root.path(Self::file()).exists() {
+            return Ok(PreparationState::default());
+        }
+
+        let content = root.read_to_string(Self::file())?;
+        let state = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid preparation state {}: {}", Self::file(), e))?;
+        Ok(state)
+    }
+
+    pub fn save(&self, root: &SystemRoot) -> Result<(), Box<dyn Error>> {
+        let dir = root.path(STATE_DIR);
+        fs::create_dir_all(&dir).map_err(|e| FileError::new("create directory", &dir.to_string_lossy(), e))?;
+        root.write(Self::file(), &serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// The fix of the requirement that is waiting for a reboot, if any.
+    pub fn pending(&self, requirement: &str) -> Option<&AppliedFix> {
+        self.applied.iter().rev().find(|fix| fix.requirement == requirement && fix.is_pending())
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.applied.iter().any(AppliedFix::is_pending)
+    }
+
+    pub fn record(&mut self, requirement: &str, journal_id: Option<String>, boot_id: Option<String>, outcome: FixOutcome) {
+        self.applied.push(AppliedFix {
+            requirement: requirement.to_string(),
+            journal_id,
+            applied_at: Utc::now(),
+            boot_id,
+            outcome,
+            confirmed_at: None,
+        });
+    }
+
+    /// Records whether the pending fixes of the requirement took effect after the reboot.
+    pub fn confirm(&mut self, requirement: &str, took_effect: bool) {
+        let outcome = if took_effect { FixOutcome::FixedNow } else { FixOutcome::NotResolved };
+        for fix in self.applied.iter_mut().filter(|fix| fix.requirement == requirement && fix.is_pending()) {
+            fix.outcome = outcome;
+            fix.confirmed_at = Some(Utc::now());
+        }
+    }
+
+    /// Forgets a fix that was undone, so it is no longer waiting for a reboot.
+    pub fn forget(&mut self, journal_id: &str) {
+        self.applied.retain(|fix| fix.journal_id.as_deref() != Some(journal_id));
+    }
+}
+
+/// Identifies the current boot of the host. An alternate root is not booted, so it has none.
+pub fn boot_id(root: &SystemRoot) -> Option<String> {
+    if !root.is_host() {
+        return None;
+    }
+    fs::read_to_string("/proc/sys/kernel/random/boot_id").ok().map(|id| id.trim().to_string())
+}
+
+/// Installs and enables a one-shot systemd unit that runs the checks once after the
+/// next boot, so the pending fixes are confirmed without the operator rerunning the tool.
+/// The unit is enabled through the `wants` symlink, which also works for an alternate root.
+pub fn install_resume_unit(root: &SystemRoot, program: &Path) -> Result<(), FileError> {
+    let program = resume_program(root, program)?;
+    let unit = format!(
+        "[Unit]\n\
+         Description=Confirm the fixes applied by vpc-migration-tools after the reboot\n\
+         After=local-fs.target\n\
+         ConditionPathExists={state_dir}/state.json\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart={program} check-requirements --no-fix --format json --output {state_dir}/resume-report.json\n\
+         SuccessExitStatus=2 3 4\n\
+         \n\
+         [Install]\n\
+         WantedBy=multi-user.target\n",
+        state_dir = STATE_DIR,
+        program = program.display(),
+    );
+
+    let unit_path = format!("{}/{}", UNIT_DIR, RESUME_UNIT);
+    let wants_dir = root.path(format!("{}/multi-user.target.wants", UNIT_DIR));
+    fs::create_dir_all(&wants_dir).map_err(|e| FileError::new("create directory", &wants_dir.to_string_lossy(), e))?;
+    root.write(&unit_path, &unit)?;
+
+    let link = wants_dir.join(RESUME_UNIT);
+    if fs::symlink_metadata(&link).is_err() {
+        std::os::unix::fs::symlink(&unit_path, &link).map_err(|e| FileError::new("enable", &unit_path, e))?;
+    }
+    Ok(())
+}
+
+/// Removes the resume unit once nothing waits for a reboot. Returns true if it was installed.
+pub fn remove_resume_unit(root: &SystemRoot) -> Result<bool, FileError> {
+    let unit_path = root.path(format!("{}/{}", UNIT_DIR, RESUME_UNIT));
+    let link = root.path(format!("{}/multi-user.target.wants/{}", UNIT_DIR, RESUME_UNIT));
+    if !unit_path.exists() {
+        return Ok(false);
+    }
+
+    for path in [&link, &unit_path] {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(FileError::new("remove", &path.to_string_lossy(), e))
+            }
+            _ => {}
+        }
+    }
+    match fs::remove_file(root.path(RESUME_PROGRAM)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(FileError::new("remove", RESUME_PROGRAM, e)),
+        _ => Ok(true),
+    }
+}
+
+/// The program the unit runs, a copy of the program in the target system. The program is not
+/// copied when it already runs from the copy, e.g. in the unit itself.
+fn resume_program(root: &SystemRoot, program: &Path) -> Result<PathBuf, FileError> {
+    let target = root.path(RESUME_PROGRAM);
+    let is_copy = target.canonicalize().is_ok_and(|target| program.canonicalize().is_ok_and(|program| program == target));
+    if !is_copy {
+        let dir = target.parent().expect("the program is in a directory");
+        fs::create_dir_all(dir).map_err(|e| FileError::new("create directory", &dir.to_string_lossy(), e))?;
+        fs::copy(program, &target).map_err(|e| FileError::new("copy", &program.to_string_lossy(), e))?;
+    }
+    Ok(PathBuf::from(RESUME_PROGRAM))
+}
+
+#[test]
+fn test_pending_fix_is_confirmed_after_reboot() {
+    let root = crate::tests::fixture_root("preparation-state", &[]);
+    let mut state = PreparationState::load(&root).unwrap();
+    state.record("kernel-args", Some("20231018-101500-kernel-args".to_string()), Some("boot-1".to_string()), FixOutcome::PendingReboot);
+    state.save(&root).unwrap();
+
+    let mut state = PreparationState::load(&root).unwrap();
+    assert_eq!(state.pending("kernel-args").unwrap().boot_id.as_deref(), Some("boot-1"));
+
+    state.confirm("kernel-args", true);
+
+    assert!(!state.has_pending());
+    assert_eq!(state.applied[0].outcome, FixOutcome::FixedNow);
+}
+
+#[test]
+fn test_resume_unit_is_installed_and_removed() {
+    let host = crate::tests::fixture_root("resume-unit-host", &[("/usr/local/bin/vpc-migration-tools", "#!/bin/sh\n")]);
+    let root = crate::tests::fixture_root("resume-unit", &[]);
+
+    install_resume_unit(&root, &host.path("/usr/local/bin/vpc-migration-tools")).unwrap();
+
+    let unit = root.read_to_string(format!("{}/{}", UNIT_DIR, RESUME_UNIT)).unwrap();
+    assert!(unit.contains(&format!("ExecStart={} check-requirements --no-fix", RESUME_PROGRAM)));
+    assert_eq!(root.read_to_string(RESUME_PROGRAM).unwrap(), "#!/bin/sh\n");
+    install_resume_unit(&root, &root.path(RESUME_PROGRAM)).unwrap();
+    assert_eq!(root.read_to_string(RESUME_PROGRAM).unwrap(), "#!/bin/sh\n");
+    assert!(fs::symlink_metadata(root.path(format!("{}/multi-user.target.wants/{}", UNIT_DIR, RESUME_UNIT))).is_ok());
+    assert!(remove_resume_unit(&root).unwrap());
+    assert!(!root.path(RESUME_PROGRAM).exists());
+    assert!(!remove_resume_unit(&root).unwrap());
+}
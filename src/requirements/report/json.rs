@@ -0,0 +1,30 @@
+use crate::requirements::report::Report;
+
+pub fn render(report: &Report) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(report)
+}
+
+#[test]
+fn test_render_json_report() {
+    use serde_json::json;
+
+    use crate::requirements::report::{RequirementReport, Status};
+    use crate::requirements::SystemRoot;
+
+    let report = Report::new(&SystemRoot::host(), false, vec![
+        RequirementReport {
+            message: "Kernel arguments are not supported".to_string(),
+            details: json!({ "missing_args": ["console=ttyS0"] }),
+            fixable: true,
+            ..RequirementReport::test("kernel-args", Status::Failed)
+        },
+    ]);
+
+    let value: serde_json::Value = serde_json::from_str(&render(&report).unwrap()).unwrap();
+
+    assert_eq!(value["schema_version"], crate::requirements::report::SCHEMA_VERSION);
+    assert_eq!(value["passed"], false);
+    assert_eq!(value["requirements"][0]["name"], "kernel-args");
+    assert_eq!(value["requirements"][0]["status"], "failed");
+    assert_eq!(value["requirements"][0]["details"]["missing_args"][0], "console=ttyS0");
+}
@@ -0,0 +1,126 @@
+use std::fmt::Write;
+
+use crate::requirements::report::{Report, RequirementReport, Status};
+
+/// Renders the report as JUnit XML. Every requirement is a test case: a failed
+/// requirement is a failure, unless a fix resolved it, and a requirement that could not
+/// be evaluated is skipped.
+pub fn render(report: &Report) -> String {
+    let tests = report.requirements.len();
+    let failures = report
+        .requirements
+        .iter()
+        .filter(|requirement| requirement.status == Status::Failed && !requirement.resolved())
+        .count();
+    let skipped = report.count(Status::Error);
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"vpc-migration-tools\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\">",
+        tests, failures, skipped
+    );
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"check-requirements\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\">",
+        tests, failures, skipped
+    );
+    xml.push_str("    <properties>\n");
+    let _ = writeln!(xml, "      <property name=\"root\" value=\"{}\"/>", escape(&report.root));
+    let _ = writeln!(xml, "      <property name=\"tool_version\" value=\"{}\"/>", escape(report.tool_version));
+    xml.push_str("    </properties>\n");
+
+    for requirement in &report.requirements {
+        render_test_case(&mut xml, requirement);
+    }
+
+    xml.push_str("  </testsuite>\n");
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn render_test_case(xml: &mut String, requirement: &RequirementReport) {
+    let _ = write!(
+        xml,
+        "    <testcase classname=\"requirements\" name=\"{}\"",
+        escape(&requirement.name)
+    );
+
+    match requirement.status {
+        Status::Passed => xml.push_str("/>\n"),
+        Status::Failed if requirement.resolved() => {
+            let outcome = requirement.fix_outcome.map(|outcome| outcome.to_string()).unwrap_or_default();
+            let _ = writeln!(
+                xml,
+                ">\n      <system-out>{}: {}</system-out>\n    </testcase>",
+                escape(&requirement.message),
+                escape(&outcome)
+            );
+        }
+        Status::Failed => {
+            let message = match requirement.fix_outcome {
+                Some(outcome) => format!("{} ({})", requirement.message, outcome),
+                None => requirement.message.clone(),
+            };
+            let _ = writeln!(
+                xml,
+                ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                escape(&message),
+                escape(&requirement.details.to_string())
+            );
+        }
+        Status::Error => {
+            let errors: Vec<String> = requirement.errors.iter().map(|error| error.chain()).collect();
+            let _ = writeln!(
+                xml,
+                ">\n      <skipped message=\"{}\">{}</skipped>\n    </testcase>",
+                escape(&requirement.message),
+                escape(&errors.join("\n"))
+            );
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // control characters other than whitespace are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn test_render_junit_report() {
+    use serde_json::json;
+
+    use crate::requirements::SystemRoot;
+
+    let requirement = |name: &str, status: Status, message: &str| RequirementReport {
+        message: message.to_string(),
+        details: json!({}),
+        ..RequirementReport::test(name, status)
+    };
+
+    let report = Report::new(&SystemRoot::host(), false, vec![
+        requirement("os-support", Status::Passed, "ubuntu, 22.04 is supported"),
+        requirement("kernel-args", Status::Failed, "Missing arguments: <console=ttyS0>"),
+        requirement("dhcp-enabled", Status::Error, "Failed to read directory: /etc/netplan/"),
+    ]);
+
+    let xml = render(&report);
+
+    assert!(xml.contains("tests=\"3\" failures=\"1\" errors=\"0\" skipped=\"1\""));
+    assert!(xml.contains("<testcase classname=\"requirements\" name=\"os-support\"/>"));
+    assert!(xml.contains("<failure message=\"Missing arguments: &lt;console=ttyS0&gt;\">"));
+    assert!(xml.contains("<skipped message=\"Failed to read directory: /etc/netplan/\">"));
+}
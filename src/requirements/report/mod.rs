@@ -0,0 +1,300 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::requirements::{Severity, SystemRoot};
+use crate::requirements::fix::{Change, RolledBack};
+
+pub mod json;
+pub mod junit;
+pub mod sarif;
+pub mod text;
+
+/// Version of the report schema. It must be bumped whenever a field is renamed,
+/// removed or changes meaning, so consumers can detect incompatible reports.
+pub const SCHEMA_VERSION: u32 = 13;
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub schema_version: u32,
+    pub tool_version: &'static str,
+    pub root: String,
+    /// True when fixes were only previewed, so their changes were not applied.
+    pub dry_run: bool,
+    pub passed: bool,
+    /// True when some fixes only take effect after the system is rebooted.
+    pub reboot_required: bool,
+    pub requirements: Vec<RequirementReport>,
+}
+
+impl Report {
+    pub fn new(root: &SystemRoot, dry_run: bool, requirements: Vec<RequirementReport>) -> Self {
+        Report {
+            schema_version: SCHEMA_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION"),
+            root: root.root().display().to_string(),
+            dry_run,
+            passed: requirements.iter().all(RequirementReport::resolved),
+            reboot_required: requirements
+                .iter()
+                .any(|requirement| requirement.fix_outcome == Some(FixOutcome::PendingReboot)),
+            requirements,
+        }
+    }
+
+    pub fn count(&self, status: Status) -> usize {
+        self.requirements.iter().filter(|requirement| requirement.status == status).count()
+    }
+
+    /// Derives the overall readiness from the outcome and severity of each requirement.
+    /// Failures resolved by a fix do not count, even when the fix is pending a reboot.
+    pub fn readiness(&self) -> Readiness {
+        let failed = |severity: Severity| {
+            self.requirements.iter().any(|requirement| {
+                requirement.status == Status::Failed && !requirement.resolved() && requirement.severity == severity
+            })
+        };
+
+        if failed(Severity::Blocker) {
+            Readiness::Blocked
+        } else if self.count(Status::Error) > 0 {
+            Readiness::NotEvaluated
+        } else if failed(Severity::Warning) {
+            Readiness::WarningsOnly
+        } else {
+            Readiness::Ready
+        }
+    }
+}
+
+/// Overall verdict of a run, which is also the process exit code.
+/// Exit code 1 is left for errors of the tool itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Readiness {
+    /// Every requirement passed or was fixed.
+    Ready = 0,
+    /// Only requirements with the warning severity failed.
+    WarningsOnly = 2,
+    /// At least one requirement with the blocker severity failed.
+    Blocked = 3,
+    /// Nothing is blocking, but at least one requirement could not be evaluated.
+    NotEvaluated = 4,
+}
+
+/// Outcome of a single requirement, as collected by `run_requirements`.
+#[derive(Debug, Serialize)]
+pub struct RequirementReport {
+    pub name: String,
+    pub status: Status,
+    pub severity: Severity,
+    pub message: String,
+    /// Values measured by the check, e.g. the missing kernel arguments.
+    pub details: Value,
+    pub remediation: Option<String>,
+    pub documentation_url: String,
+    pub fixable: bool,
+    pub fix_applied: bool,
+    /// What the check found when it ran again after the fix was applied.
+    pub fix_outcome: Option<FixOutcome>,
+    /// Set when a previous run applied a fix that was waiting for a reboot.
+    pub resume: Option<ResumeOutcome>,
+    /// The changes made by the fix, or the ones it would make in dry-run mode.
+    pub changes: Vec<Change>,
+    /// The compensating actions executed because the fix failed.
+    pub rolled_back: Vec<RolledBack>,
+    /// The journal entry of the applied fix, to undo it with `undo --id`.
+    pub journal_id: Option<String>,
+    pub errors: Vec<ErrorReport>,
+}
+
+impl RequirementReport {
+    /// True when the requirement passed, or a fix resolved it, possibly after a reboot.
+    pub fn resolved(&self) -> bool {
+        self.status == Status::Passed
+            || matches!(self.fix_outcome, Some(FixOutcome::FixedNow) | Some(FixOutcome::PendingReboot))
+    }
+}
+
+#[cfg(test)]
+impl RequirementReport {
+    /// A blocking requirement without a fix, for the tests to complete with the fields they look at.
+    pub fn test(name: &str, status: Status) -> Self {
+        RequirementReport {
+            name: name.to_string(),
+            status,
+            severity: Severity::Blocker,
+            message: String::new(),
+            details: Value::Null,
+            remediation: None,
+            documentation_url: String::new(),
+            fixable: false,
+            fix_applied: false,
+            fix_outcome: None,
+            resume: None,
+            changes: vec![],
+            rolled_back: vec![],
+            journal_id: None,
+            errors: vec![],
+        }
+    }
+}
+
+/// Result of the check that runs again after a fix was applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixOutcome {
+    /// The requirement passes now.
+    FixedNow,
+    /// The check still fails because it observes the running system,
+    /// e.g. the kernel arguments of the current boot, so the fix shows after a reboot.
+    PendingReboot,
+    /// The requirement still fails after the fix.
+    NotResolved,
+}
+
+/// What a run found for a fix that a previous run applied and that was waiting for a reboot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResumeOutcome {
+    /// The system was not rebooted since the fix was applied.
+    StillPendingReboot,
+    TookEffect,
+    DidNotTakeEffect,
+}
+
+impl Display for FixOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixOutcome::FixedNow => write!(f, "fixed now"),
+            FixOutcome::PendingReboot => write!(f, "fixed, pending reboot"),
+            FixOutcome::NotResolved => write!(f, "not resolved by the fix"),
+        }
+    }
+}
+
+/// An error raised while checking or fixing a requirement, with its chain of causes.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub stage: Stage,
+    pub message: String,
+    /// The underlying errors, from the outermost one to the root cause.
+    pub causes: Vec<String>,
+}
+
+impl ErrorReport {
+    pub fn new(stage: Stage, error: &dyn Error) -> Self {
+        let mut causes = vec![];
+        let mut source = error.source();
+        while let Some(cause) = source {
+            causes.push(cause.to_string());
+            source = cause.source();
+        }
+
+        ErrorReport { stage, message: error.to_string(), causes }
+    }
+
+    /// The message followed by its causes, e.g. `Failed to read /etc/netplan/: No such file or directory`.
+    pub fn chain(&self) -> String {
+        std::iter::once(&self.message)
+            .chain(self.causes.iter())
+            .map(String::as_str)
+            .collect::<Vec<&str>>()
+            .join(": ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Check,
+    Fix,
+    /// Recording the applied fix in the journal.
+    Journal,
+    /// Checking the requirement again after the fix.
+    Recheck,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Passed,
+    Failed,
+    /// The check itself failed, so the requirement could not be evaluated.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Junit,
+    Sarif,
+}
+
+impl OutputFormat {
+    pub const VARIANTS: &'static [&'static str] = &["text", "json", "junit", "sarif"];
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "junit" => Ok(OutputFormat::Junit),
+            "sarif" => Ok(OutputFormat::Sarif),
+            _ => Err(format!("Unknown output format: {}", s)),
+        }
+    }
+}
+
+pub fn render(format: OutputFormat, report: &Report) -> Result<String, Box<dyn Error>> {
+    match format {
+        OutputFormat::Text => Ok(text::render(report)),
+        OutputFormat::Json => Ok(json::render(report)?),
+        OutputFormat::Junit => Ok(junit::render(report)),
+        OutputFormat::Sarif => Ok(sarif::render(report)?),
+    }
+}
+
+#[test]
+fn test_readiness_from_severity() {
+    let requirement = |status: Status, severity: Severity, fix_outcome: Option<FixOutcome>| RequirementReport {
+        severity,
+        fixable: fix_outcome.is_some(),
+        fix_applied: fix_outcome.is_some(),
+        fix_outcome,
+        ..RequirementReport::test("requirement", status)
+    };
+    let readiness = |requirements| Report::new(&SystemRoot::host(), false, requirements).readiness();
+
+    assert_eq!(readiness(vec![requirement(Status::Passed, Severity::Blocker, None)]), Readiness::Ready);
+    assert_eq!(readiness(vec![requirement(Status::Failed, Severity::Blocker, Some(FixOutcome::FixedNow))]), Readiness::Ready);
+    assert_eq!(readiness(vec![requirement(Status::Failed, Severity::Blocker, Some(FixOutcome::PendingReboot))]), Readiness::Ready);
+    assert_eq!(readiness(vec![requirement(Status::Failed, Severity::Blocker, Some(FixOutcome::NotResolved))]), Readiness::Blocked);
+    assert_eq!(readiness(vec![requirement(Status::Failed, Severity::Warning, None)]), Readiness::WarningsOnly);
+    assert_eq!(readiness(vec![
+        requirement(Status::Failed, Severity::Warning, None),
+        requirement(Status::Error, Severity::Blocker, None),
+    ]), Readiness::NotEvaluated);
+    assert_eq!(readiness(vec![
+        requirement(Status::Error, Severity::Blocker, None),
+        requirement(Status::Failed, Severity::Blocker, None),
+    ]), Readiness::Blocked);
+}
+
+#[test]
+fn test_error_report_keeps_causes() {
+    use crate::utils::FileError;
+
+    let error = FileError::new("read directory", "/etc/netplan/", std::io::ErrorKind::NotFound.into());
+    let report = ErrorReport::new(Stage::Check, &error);
+
+    assert_eq!(report.message, "Failed to read directory /etc/netplan/");
+    assert_eq!(report.causes, vec!["entity not found"]);
+    assert_eq!(report.chain(), "Failed to read directory /etc/netplan/: entity not found");
+}
@@ -0,0 +1,109 @@
+use serde_json::{json, Value};
+
+use crate::requirements::report::{Report, RequirementReport, Status};
+use crate::requirements::Severity;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/usherlabs/vpc-migration-tools";
+
+/// Renders the report as a SARIF 2.1.0 log. Each requirement is a rule and has
+/// exactly one result, so passing requirements show up as `pass` results.
+pub fn render(report: &Report) -> Result<String, serde_json::Error> {
+    let rules: Vec<Value> = report.requirements.iter().map(rule).collect();
+    let results: Vec<Value> = report
+        .requirements
+        .iter()
+        .enumerate()
+        .map(|(index, requirement)| result(index, requirement))
+        .collect();
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "vpc-migration-tools",
+                    "version": report.tool_version,
+                    "informationUri": INFORMATION_URI,
+                    "rules": rules,
+                }
+            },
+            "properties": {
+                "root": report.root,
+                "schemaVersion": report.schema_version,
+            },
+            "results": results,
+        }]
+    });
+
+    serde_json::to_string_pretty(&log)
+}
+
+fn rule(requirement: &RequirementReport) -> Value {
+    json!({
+        "id": requirement.name,
+        "name": requirement.name,
+        "shortDescription": { "text": format!("Requirement {}", requirement.name) },
+        "help": { "text": requirement.remediation.as_deref().unwrap_or(&requirement.message) },
+        "helpUri": requirement.documentation_url,
+    })
+}
+
+fn result(index: usize, requirement: &RequirementReport) -> Value {
+    // `open` means the tool could not determine whether the problem exists,
+    // and SARIF only allows a level other than `none` for `fail` results
+    let (kind, level) = match (requirement.status, requirement.severity) {
+        (Status::Passed, _) => ("pass", "none"),
+        (Status::Failed, Severity::Blocker) => ("fail", "error"),
+        (Status::Failed, Severity::Warning) => ("fail", "warning"),
+        (Status::Failed, Severity::Info) => ("fail", "note"),
+        (Status::Error, _) => ("open", "none"),
+    };
+
+    json!({
+        "ruleId": requirement.name,
+        "ruleIndex": index,
+        "kind": kind,
+        "level": level,
+        "message": { "text": requirement.message },
+        "locations": [{
+            "logicalLocations": [{ "name": requirement.name, "kind": "module" }]
+        }],
+        "properties": {
+            "details": requirement.details,
+            "fixable": requirement.fixable,
+            "fixApplied": requirement.fix_applied,
+            "fixOutcome": requirement.fix_outcome,
+            "resume": requirement.resume,
+            "rolledBack": requirement.rolled_back,
+            "journalId": requirement.journal_id,
+            "errors": requirement.errors,
+        },
+    })
+}
+
+#[test]
+fn test_render_sarif_report() {
+    use crate::requirements::SystemRoot;
+
+    let requirement = |name: &str, status: Status, message: &str| RequirementReport {
+        message: message.to_string(),
+        details: json!({ "installed": false }),
+        ..RequirementReport::test(name, status)
+    };
+    let report = Report::new(&SystemRoot::host(), false, vec![
+        requirement("cloud-init", Status::Failed, "Cloud-init is not installed"),
+        requirement("boot-disk-size", Status::Error, "Failed to read the partitions"),
+    ]);
+
+    let log: Value = serde_json::from_str(&render(&report).unwrap()).unwrap();
+
+    assert_eq!(log["version"], "2.1.0");
+    assert_eq!(log["runs"][0]["tool"]["driver"]["rules"][0]["id"], "cloud-init");
+    assert_eq!(log["runs"][0]["results"][0]["kind"], "fail");
+    assert_eq!(log["runs"][0]["results"][0]["level"], "error");
+    assert_eq!(log["runs"][0]["results"][0]["message"]["text"], "Cloud-init is not installed");
+    assert_eq!(log["runs"][0]["results"][1]["kind"], "open");
+    assert_eq!(log["runs"][0]["results"][1]["level"], "none");
+}
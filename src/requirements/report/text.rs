@@ -0,0 +1,231 @@
+use std::fmt::Write;
+
+use crate::requirements::report::{FixOutcome, Readiness, Report, RequirementReport, ResumeOutcome, Status};
+use crate::requirements::fix::Change;
+use crate::requirements::Severity;
+
+const DETAIL_WIDTH: usize = 60;
+
+/// Renders the end-of-run summary for operators: a table with every requirement,
+/// the requirements that need attention with their remediation, and the overall verdict.
+pub fn render(report: &Report) -> String {
+    let mut text = render_table(report);
+
+    let mut attention: Vec<&RequirementReport> = report
+        .requirements
+        .iter()
+        .filter(|requirement| !requirement.resolved())
+        .collect();
+    attention.sort_by_key(|requirement| rank(requirement));
+
+    if !attention.is_empty() {
+        text.push_str("\nRequirements that need attention:\n");
+    }
+    for requirement in attention {
+        let label = match requirement.status {
+            Status::Error => "not evaluated".to_string(),
+            _ => requirement.severity.to_string(),
+        };
+
+        let _ = writeln!(text, "\n[{}] {}: {}", label, requirement.name, requirement.message);
+        if requirement.fix_outcome == Some(FixOutcome::NotResolved) {
+            let _ = writeln!(text, "    The fix was applied, but the requirement still fails.");
+        }
+        if requirement.resume == Some(ResumeOutcome::DidNotTakeEffect) {
+            let _ = writeln!(text, "    The fix applied before the reboot did not take effect.");
+        }
+        if let Some(remediation) = &requirement.remediation {
+            let _ = writeln!(text, "    Remediation: {}", remediation);
+        }
+        let _ = writeln!(text, "    Documentation: {}", requirement.documentation_url);
+    }
+
+    for requirement in report.requirements.iter().filter(|requirement| !requirement.changes.is_empty()) {
+        render_changes(&mut text, report.dry_run, requirement);
+    }
+
+    for requirement in report.requirements.iter().filter(|requirement| !requirement.rolled_back.is_empty()) {
+        let _ = writeln!(text, "\nThe fix of {} failed and was rolled back:", requirement.name);
+        for rolled_back in &requirement.rolled_back {
+            match &rolled_back.error {
+                None => { let _ = writeln!(text, "  {}", rolled_back.action); }
+                Some(e) => { let _ = writeln!(text, "  {} FAILED: {}", rolled_back.action, e); }
+            }
+        }
+    }
+
+    let confirmed: Vec<&str> = report
+        .requirements
+        .iter()
+        .filter(|requirement| requirement.resume == Some(ResumeOutcome::TookEffect))
+        .map(|requirement| requirement.name.as_str())
+        .collect();
+    if !confirmed.is_empty() {
+        let _ = writeln!(text, "\nFixes confirmed after the reboot: {}", confirmed.join(", "));
+    }
+
+    if report.reboot_required {
+        let pending: Vec<&str> = report
+            .requirements
+            .iter()
+            .filter(|requirement| requirement.fix_outcome == Some(FixOutcome::PendingReboot))
+            .map(|requirement| requirement.name.as_str())
+            .collect();
+        let _ = writeln!(text, "\nReboot the system to apply the fixes of: {}", pending.join(", "));
+    }
+
+    let _ = writeln!(text, "\n{}", verdict(report.readiness()));
+    text
+}
+
+fn render_changes(text: &mut String, dry_run: bool, requirement: &RequirementReport) {
+    if dry_run {
+        let _ = writeln!(text, "\nChanges the fix of {} would make:", requirement.name);
+    } else {
+        let _ = writeln!(text, "\nChanges made by the fix of {}:", requirement.name);
+    }
+
+    for change in &requirement.changes {
+        match change {
+            Change::WriteFile { path, diff } => {
+                let _ = writeln!(text, "  modify {}", path);
+                for line in diff.lines() {
+                    let _ = writeln!(text, "    {}", line);
+                }
+            }
+            Change::RunCommand { command } => {
+                let _ = writeln!(text, "  run {}", command);
+            }
+        }
+    }
+
+    if let Some(id) = &requirement.journal_id {
+        let _ = writeln!(text, "  Recorded as {}, revert with: vpc-migration-tools undo --id {}", id, id);
+    }
+}
+
+fn render_table(report: &Report) -> String {
+    let header = ["REQUIREMENT", "STATUS", "DETAIL", "FIXABLE", "FIX APPLIED"];
+    let rows: Vec<[String; 5]> = report
+        .requirements
+        .iter()
+        .map(|requirement| {
+            [
+                requirement.name.clone(),
+                status_label(requirement.status).to_string(),
+                truncate(&requirement.message, DETAIL_WIDTH),
+                yes_no(requirement.fixable).to_string(),
+                fix_applied_label(report.dry_run, requirement).to_string(),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(|title| title.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    let mut write_row = |cells: [&str; 5]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        let _ = writeln!(table, "{}", line.join("  ").trim_end());
+    };
+
+    write_row(header);
+    for row in &rows {
+        write_row([&row[0], &row[1], &row[2], &row[3], &row[4]]);
+    }
+
+    table
+}
+
+fn verdict(readiness: Readiness) -> &'static str {
+    match readiness {
+        Readiness::Ready => "READY: the system can be used to create an image.",
+        Readiness::WarningsOnly => "READY WITH WARNINGS: the image will work, but some features might not.",
+        Readiness::Blocked => "NOT READY: resolve the blocking requirements before creating an image.",
+        Readiness::NotEvaluated => "UNKNOWN: some requirements could not be evaluated.",
+    }
+}
+
+fn status_label(status: Status) -> &'static str {
+    match status {
+        Status::Passed => "passed",
+        Status::Failed => "failed",
+        Status::Error => "not evaluated",
+    }
+}
+
+fn fix_applied_label(dry_run: bool, requirement: &RequirementReport) -> &'static str {
+    if !requirement.fixable {
+        "-"
+    } else if dry_run && !requirement.changes.is_empty() {
+        "dry run"
+    } else {
+        match requirement.fix_outcome {
+            Some(FixOutcome::PendingReboot) => "after reboot",
+            Some(FixOutcome::NotResolved) => "not resolved",
+            _ => yes_no(requirement.fix_applied),
+        }
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+fn truncate(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        value.to_string()
+    } else {
+        let truncated: String = value.chars().take(width - 3).collect();
+        format!("{}...", truncated)
+    }
+}
+
+/// Orders blockers first, then requirements that could not be evaluated, then warnings.
+fn rank(requirement: &RequirementReport) -> u8 {
+    match (requirement.status, requirement.severity) {
+        (Status::Error, _) => 1,
+        (_, Severity::Blocker) => 0,
+        (_, Severity::Warning) => 2,
+        (_, Severity::Info) => 3,
+    }
+}
+
+#[test]
+fn test_render_text_summary() {
+    use serde_json::json;
+
+    use crate::requirements::SystemRoot;
+
+    let requirement = |name: &str, status: Status, severity: Severity| RequirementReport {
+        severity,
+        message: format!("{} message", name),
+        details: json!({}),
+        remediation: Some(format!("{} remediation", name)),
+        documentation_url: "https://cloud.ibm.com/docs/vpc".to_string(),
+        ..RequirementReport::test(name, status)
+    };
+
+    let report = Report::new(&SystemRoot::host(), false, vec![
+        requirement("cloud-init", Status::Failed, Severity::Warning),
+        requirement("os-support", Status::Failed, Severity::Blocker),
+        requirement("dhcp-enabled", Status::Passed, Severity::Info),
+    ]);
+
+    let text = render(&report);
+
+    assert!(text.find("[blocker] os-support").unwrap() < text.find("[warning] cloud-init").unwrap());
+    assert!(text.contains("    Remediation: cloud-init remediation"));
+    assert!(!text.contains("] dhcp-enabled"));
+    assert!(text.starts_with("REQUIREMENT   STATUS  DETAIL"));
+    assert!(text.contains("\ndhcp-enabled  passed  dhcp-enabled message  no       -\n"));
+    assert!(text.ends_with("NOT READY: resolve the blocking requirements before creating an image.\n"));
+}
@@ -1,10 +1,71 @@
 use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
 use dialoguer::Confirm;
-use crate::requirements::{CheckResult, checks, Requirement};
+use serde_json::Value;
+
+use crate::requirements::{checks, DOCUMENTATION_URL, FixContext, Requirement, Severity, SystemRoot};
+use crate::requirements::os_matrix::OsMatrix;
+use crate::requirements::preparation::{self, PreparationState};
+use crate::requirements::report::{self, ErrorReport, FixOutcome, OutputFormat, Report, RequirementReport, ResumeOutcome, Stage, Status};
+
+pub struct Options {
+    pub root: SystemRoot,
+    pub format: OutputFormat,
+    pub output: Option<PathBuf>,
+    pub fix_mode: FixMode,
+    /// Only preview the changes of the fixes instead of applying them.
+    pub dry_run: bool,
+    /// Install a one-shot systemd unit that confirms the fixes pending a reboot after the next boot.
+    pub resume_unit: bool,
+    /// The supported operating systems, the embedded matrix unless overridden with `--os-matrix`.
+    pub os_matrix: OsMatrix,
+}
+
+/// Decides which fixes are applied to the failed requirements.
+pub enum FixMode {
+    /// Ask the user before applying each available fix.
+    Prompt,
+    /// Apply every available fix without asking.
+    All,
+    /// Apply only the fixes of the named requirements, without asking.
+    Only(Vec<String>),
+    /// Only report, never apply a fix.
+    Never,
+}
+
+impl FixMode {
+    fn should_apply(&self, requirement: &dyn Requirement) -> bool {
+        match self {
+            FixMode::Prompt => Confirm::new()
+                .with_prompt("Do you want to apply this fix?")
+                .interact()
+                .unwrap_or(false),
+            FixMode::All => true,
+            FixMode::Only(names) => names.iter().any(|name| name == requirement.name()),
+            FixMode::Never => false,
+        }
+    }
+}
+
+/// Runs every requirement, applies the fixes selected by the fix mode and writes the report.
+/// The exit code is the readiness of the system, see [`report::Readiness`].
+pub fn run_requirements(options: Options) -> Result<ExitCode, Box<dyn Error>> {
+    let Options { root, format, output, fix_mode, dry_run, resume_unit, os_matrix } = options;
+
+    // previewing does not change anything, so there is no need to ask
+    let fix_mode = match fix_mode {
+        FixMode::Prompt if dry_run => FixMode::All,
+        fix_mode => fix_mode,
+    };
 
-pub fn run_requirements() -> Result<(), Box<dyn Error>> {
     log::info!("");
-    log::info!("The requirements are based on the documentation available at https://cloud.ibm.com/docs/vpc?topic=vpc-create-linux-custom-image");
+    if !root.is_host() {
+        log::info!("Checking the system tree at {}", root.root().display());
+    }
+    log::info!("The requirements are based on the documentation available at {}", DOCUMENTATION_URL);
 
     // let vitio_check = checks::virtio_drivers::VitioDriversRequirement.check()?;
     // let os_support_check = checks::os_support::OSSupportRequirement.check()?;
@@ -23,40 +84,226 @@ pub fn run_requirements() -> Result<(), Box<dyn Error>> {
     // ];
 
     let requirements: Vec<Box<dyn Requirement>> = vec![
-        Box::new(checks::os_support::OSSupportRequirement),
+        Box::new(checks::os_support::OSSupportRequirement { matrix: os_matrix }),
         Box::new(checks::cloud_init::CloudInitRequirement),
         Box::new(checks::virtio_drivers::VitioDriversRequirement),
         Box::new(checks::boot_disk_size::BootDiskSizeRequirement),
         Box::new(checks::dhcp_enabled::DhcpEnabledRequirement),
+        Box::new(checks::mac_bindings::MacBindingsRequirement),
         Box::new(checks::kernel_args::KernelArgsRequirement),
     ];
 
+    if let FixMode::Only(names) = &fix_mode {
+        validate_fix_names(names, &requirements)?;
+    }
 
-    requirements
-        .into_iter()
-        .map(|requirement| (requirement.check(), requirement))
-        .filter_map(|(result, requirement)| match result {
-            Ok(res) if !res.passed() => Some((res, requirement)),
-            _ => None,
+    let mut state = PreparationState::load(&root)?;
+    let had_state = !state.applied.is_empty();
+    let boot_id = preparation::boot_id(&root);
+
+    let results = requirements
+        .iter()
+        .map(|requirement| {
+            let mut run = RequirementRun { root: &root, fix_mode: &fix_mode, dry_run, state: &mut state, boot_id: &boot_id };
+            run_requirement(requirement.as_ref(), &mut run)
         })
-        .filter(|(result, requirement)| filter_fixable(requirement.as_ref()))
-        .for_each(|(result, fixable_requirement)| {
-            log::info!("\nRequirement {:?} failed. Available fix:", fixable_requirement);
+        .collect();
 
-            if Confirm::new()
-                .with_prompt("Do you want to apply this fix?")
-                .interact()
-                .unwrap_or(false)
-            {
-                match fixable_requirement.fix() {
-                    Some(Ok(_)) => log::info!("Fix applied successfully"),
-                    Some(Err(e)) => log::error!("Error applying fix: {:?}", e),
-                    None => log::info!("No fix available"),
+    let report = Report::new(&root, dry_run, results);
+
+    if !dry_run && (had_state || !state.applied.is_empty()) {
+        update_preparation(&root, &state, resume_unit)?;
+    }
+
+    let rendered = report::render(format, &report)?;
+    match output {
+        Some(path) => fs::write(&path, rendered)
+            .map_err(|e| format!("Failed to write report to {}: {}", path.display(), e))?,
+        None => println!("{}", rendered),
+    }
+
+    Ok(ExitCode::from(report.readiness() as u8))
+}
+
+fn validate_fix_names(names: &[String], requirements: &[Box<dyn Requirement>]) -> Result<(), Box<dyn Error>> {
+    let fixable: Vec<&str> = requirements
+        .iter()
+        .filter(|requirement| filter_fixable(requirement.as_ref()))
+        .map(|requirement| requirement.name())
+        .collect();
+
+    for name in names {
+        if !fixable.contains(&name.as_str()) {
+            return Err(format!("Unknown fix: {}. Available fixes: {}", name, fixable.join(", ")).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Saves the preparation state and keeps the resume unit only while fixes wait for a reboot.
+fn update_preparation(root: &SystemRoot, state: &PreparationState, resume_unit: bool) -> Result<(), Box<dyn Error>> {
+    state.save(root)?;
+
+    if state.has_pending() {
+        if resume_unit {
+            let program = std::env::current_exe()?;
+            preparation::install_resume_unit(root, &program)?;
+            log::info!("Installed {}, the pending fixes will be confirmed after the reboot", preparation::RESUME_UNIT);
+        } else {
+            log::warn!("Some fixes take effect after a reboot. Run check-requirements again after rebooting to confirm them.");
+        }
+    } else if preparation::remove_resume_unit(root)? {
+        log::info!("No fix is pending a reboot anymore, removed {}", preparation::RESUME_UNIT);
+    }
+
+    Ok(())
+}
+
+/// The state shared by the requirements of a run.
+struct RequirementRun<'a> {
+    root: &'a SystemRoot,
+    fix_mode: &'a FixMode,
+    dry_run: bool,
+    state: &'a mut PreparationState,
+    boot_id: &'a Option<String>,
+}
+
+/// Checks a single requirement and, when it fails and a fix is available,
+/// applies (or previews, in dry-run mode) the fix if the fix mode allows it.
+/// A fix applied by a previous run and waiting for a reboot is confirmed instead of applied again.
+fn run_requirement(requirement: &dyn Requirement, run: &mut RequirementRun) -> RequirementReport {
+    let RequirementRun { root, fix_mode, dry_run, .. } = *run;
+
+    let mut report = RequirementReport {
+        name: requirement.name().to_string(),
+        status: Status::Error,
+        // a requirement that could not be evaluated might be blocking
+        severity: Severity::Blocker,
+        message: String::new(),
+        details: Value::Null,
+        remediation: None,
+        documentation_url: DOCUMENTATION_URL.to_string(),
+        fixable: requirement.fixable(),
+        fix_applied: false,
+        fix_outcome: None,
+        resume: None,
+        changes: vec![],
+        rolled_back: vec![],
+        journal_id: None,
+        errors: vec![],
+    };
+
+    let result = match requirement.check(root) {
+        Ok(result) => result,
+        Err(e) => {
+            let error = ErrorReport::new(Stage::Check, e.as_ref());
+            log::error!("Requirement {} could not be evaluated: {}", report.name, error.chain());
+            report.message = format!("Could not evaluate the requirement: {}", error.chain());
+            report.errors.push(error);
+            return report;
+        }
+    };
+
+    report.message = result.message();
+    report.details = result.details();
+    report.severity = result.severity();
+    report.remediation = result.remediation();
+    report.documentation_url = result.documentation_url().to_string();
+    report.status = if result.passed() { Status::Passed } else { Status::Failed };
+
+    if let Some(pending) = run.state.pending(requirement.name()).cloned() {
+        if pending.boot_id == *run.boot_id && report.status == Status::Failed {
+            log::info!("The fix of {} applied at {} is waiting for a reboot", report.name, pending.applied_at);
+            report.resume = Some(ResumeOutcome::StillPendingReboot);
+            report.fix_applied = true;
+            report.fix_outcome = Some(FixOutcome::PendingReboot);
+            report.journal_id = pending.journal_id;
+            return report;
+        }
+
+        let took_effect = report.status == Status::Passed;
+        if took_effect {
+            log::info!("The fix of {} applied at {} took effect", report.name, pending.applied_at);
+            report.resume = Some(ResumeOutcome::TookEffect);
+        } else {
+            log::warn!("The fix of {} applied at {} did not take effect after the reboot", report.name, pending.applied_at);
+            report.resume = Some(ResumeOutcome::DidNotTakeEffect);
+        }
+        if !dry_run {
+            run.state.confirm(requirement.name(), took_effect);
+        }
+    }
+
+    if report.status == Status::Failed && filter_fixable(requirement) {
+        log::info!("\nRequirement {:?} failed. Available fix:", requirement);
+
+        if fix_mode.should_apply(requirement) {
+            let mut ctx = FixContext::new(root, dry_run);
+            let result = requirement.fix(&mut ctx);
+            report.changes = ctx.changes().to_vec();
+
+            match result {
+                // e.g. a configuration that only waits for a reboot, there is nothing to record
+                Some(Ok(false)) => log::info!("The fix has nothing to change"),
+                Some(Ok(true)) if dry_run => log::info!("Dry run, the fix was not applied"),
+                Some(Ok(true)) => {
+                    log::info!("Fix applied successfully");
+                    report.fix_applied = true;
+                    match ctx.commit(requirement.name()) {
+                        Ok(entry) => {
+                            log::info!("Fix recorded in the journal as {}", entry.id);
+                            report.journal_id = Some(entry.id);
+                        }
+                        Err(e) => {
+                            let error = ErrorReport::new(Stage::Journal, e.as_ref());
+                            log::error!("The fix cannot be undone, recording it failed: {}", error.chain());
+                            report.errors.push(error);
+                        }
+                    }
+
+                    match recheck(requirement, root) {
+                        Ok(outcome) => {
+                            report.fix_outcome = Some(outcome);
+                            run.state.record(requirement.name(), report.journal_id.clone(), run.boot_id.clone(), outcome);
+                        }
+                        Err(e) => {
+                            let error = ErrorReport::new(Stage::Recheck, e.as_ref());
+                            log::error!("Requirement {} could not be checked again: {}", report.name, error.chain());
+                            report.errors.push(error);
+                        }
+                    }
+                }
+                Some(Err(e)) => {
+                    let error = ErrorReport::new(Stage::Fix, e.as_ref());
+                    log::error!("Error applying fix: {}", error.chain());
+                    report.errors.push(error);
+                    log::warn!("Rolling back the changes of the fix");
+                    report.rolled_back = ctx.rollback();
                 }
+                None => log::info!("No fix available"),
             }
-        });
+        }
+    }
 
-    Ok(())
+    report
+}
+
+/// Runs the check again after a fix, to tell whether the fix resolved the requirement.
+fn recheck(requirement: &dyn Requirement, root: &SystemRoot) -> Result<FixOutcome, Box<dyn Error>> {
+    log::info!("Checking {} again", requirement.name());
+    let result = requirement.check(root)?;
+
+    let outcome = if result.passed() {
+        FixOutcome::FixedNow
+    } else if requirement.takes_effect_after_reboot(root) {
+        FixOutcome::PendingReboot
+    } else {
+        FixOutcome::NotResolved
+    };
+    log::info!("Requirement {}: {}", requirement.name(), outcome);
+
+    Ok(outcome)
 }
 
 fn filter_fixable(requirement: &dyn Requirement) -> bool {
@@ -0,0 +1,53 @@
+use std::error::Error;
+use std::process::ExitCode;
+
+use crate::requirements::fix::{Journal, UndoTarget};
+use crate::requirements::preparation::{self, PreparationState};
+use crate::requirements::SystemRoot;
+
+pub struct Options {
+    pub root: SystemRoot,
+    /// List the journal entries instead of undoing one.
+    pub list: bool,
+    pub target: UndoTarget,
+}
+
+/// Reverts a fix recorded in the journal of the target system, or lists the recorded fixes.
+pub fn run_undo(options: Options) -> Result<ExitCode, Box<dyn Error>> {
+    let Options { root, list, target } = options;
+    let journal = Journal::new(&root);
+
+    if list {
+        let entries = journal.entries()?;
+        if entries.is_empty() {
+            println!("No fix was recorded.");
+        }
+        for entry in entries {
+            let state = match entry.undone_at {
+                Some(undone_at) => format!("undone at {}", undone_at.to_rfc3339()),
+                None => "applied".to_string(),
+            };
+            let files: Vec<&str> = entry.files.iter().map(|file| file.path.as_str()).collect();
+            println!("{}  {}  {}  {}", entry.id, entry.requirement, state, files.join(", "));
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let entry = journal.undo(&target)?;
+    log::info!("Undid the fix of {} recorded as {}", entry.requirement, entry.id);
+
+    let mut state = PreparationState::load(&root)?;
+    state.forget(&entry.id);
+    state.save(&root)?;
+    if !state.has_pending() && preparation::remove_resume_unit(&root)? {
+        log::info!("No fix is pending a reboot anymore, removed {}", preparation::RESUME_UNIT);
+    }
+    if !entry.commands.is_empty() {
+        log::warn!(
+            "The fix also ran {}. Files they generated were restored when backed up, check that the system is consistent before rebooting.",
+            entry.commands.join(", ")
+        );
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
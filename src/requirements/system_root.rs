@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::utils::FileError;
+
+/// The filesystem tree the requirements are evaluated against.
+///
+/// By default this is the running host (`/`), but it can point to any
+/// mounted or extracted system tree, e.g. a disk that is not booted.
+/// Every absolute path used by a check must be resolved through [`SystemRoot::path`].
+#[derive(Debug, Clone)]
+pub struct SystemRoot {
+    root: PathBuf,
+}
+
+impl SystemRoot {
+    pub fn host() -> Self {
+        SystemRoot { root: PathBuf::from("/") }
+    }
+
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        SystemRoot { root: root.into() }
+    }
+
+    /// Returns true when the requirements run against the live system,
+    /// so runtime information (`/proc`, `uname`, mounted disks) is meaningful.
+    pub fn is_host(&self) -> bool {
+        self.root == Path::new("/")
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolves an absolute path of the target system to a path on the host.
+    pub fn path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        let path = path.as_ref();
+        self.root.join(path.strip_prefix("/").unwrap_or(path))
+    }
+
+    pub fn read_to_string<P: AsRef<Path>>(&self, path: P) -> Result<String, FileError> {
+        let path = self.path(path);
+        fs::read_to_string(&path).map_err(|e| FileError::new("read", &path.to_string_lossy(), e))
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P, contents: &str) -> Result<(), FileError> {
+        let path = self.path(path);
+        fs::write(&path, contents).map_err(|e| FileError::new("write", &path.to_string_lossy(), e))
+    }
+
+    /// Looks for an executable in the usual binary directories of the target system
+    /// and returns its absolute path inside the target.
+    pub fn find_program(&self, name: &str) -> Option<String> {
+        ["/usr/local/sbin", "/usr/local/bin", "/usr/sbin", "/usr/bin", "/sbin", "/bin"]
+            .iter()
+            .map(|dir| format!("{}/{}", dir, name))
+            .find(|program| self.path(program).is_file())
+    }
+
+    /// Builds a command that runs inside the target system.
+    /// On an alternate root the program is executed through `chroot`.
+    pub fn command(&self, program: &str) -> Command {
+        if self.is_host() {
+            Command::new(program)
+        } else {
+            let mut cmd = Command::new("chroot");
+            cmd.arg(&self.root).arg(program);
+            cmd
+        }
+    }
+}
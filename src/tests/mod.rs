@@ -7,4 +7,32 @@ fn test_metadata_root() -> Result<(), Box<dyn Error>> {
     }
     Ok(())
 
-}
\ No newline at end of file
+}
+
+/// Creates a throwaway system tree under the temp directory, containing the given files,
+/// so requirements can be checked against it through `--root`.
+pub fn fixture_root(name: &str, files: &[(&str, &str)]) -> crate::requirements::SystemRoot {
+    let root = std::env::temp_dir()
+        .join("vpc-migration-tools-fixtures")
+        .join(format!("{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+
+    let root = crate::requirements::SystemRoot::new(root);
+    for (path, content) in files {
+        let path = root.path(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    root
+}
+
+#[test]
+fn test_fixture_root_paths() {
+    let root = fixture_root("paths", &[("/etc/hostname", "vsi")]);
+
+    assert!(!root.is_host());
+    assert_eq!(root.read_to_string("/etc/hostname").unwrap(), "vsi");
+    assert!(root.path("/etc/hostname").starts_with(root.root()));
+}
@@ -0,0 +1,58 @@
+use std::fmt::Display;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+
+use thiserror::Error;
+
+pub mod shell_assignments;
+
+/// An I/O error on a file, keeping the path it happened on and the underlying cause.
+#[derive(Error, Debug)]
+#[error("Failed to {action} {path}")]
+pub struct FileError {
+    pub action: &'static str,
+    pub path: String,
+    #[source]
+    pub source: io::Error,
+}
+
+impl FileError {
+    pub fn new(action: &'static str, path: &str, source: io::Error) -> Self {
+        FileError { action, path: path.to_string(), source }
+    }
+}
+
+pub fn read_file_to_string(path: &str) -> Result<String, FileError> {
+    let file = File::open(path).map_err(|e| FileError::new("open", path, e))?;
+    let reader = BufReader::new(file);
+    let lines: Vec<String> = reader
+        .lines()
+        .collect::<Result<_, _>>()
+        .map_err(|e| FileError::new("read", path, e))?;
+
+    Ok(lines.join("\n"))
+}
+
+/// Compares two kernel releases by their numeric components, so `5.15.0-91` sorts after `5.15.0-9`.
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let numbers = |version: &str| -> Vec<u64> {
+        version
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|part| part.parse().ok())
+            .collect()
+    };
+
+    numbers(a).cmp(&numbers(b)).then_with(|| a.cmp(b))
+}
+
+pub struct Option {
+    pub label: String,
+    pub value: String,
+}
+
+impl Display for Option {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.label, self.value)
+    }
+}
@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ShellFileError {
+    #[error("Unterminated quote in {path} at line {line}")]
+    UnterminatedQuote { path: String, line: usize },
+}
+
+/// An assignment of a shell file.
+#[derive(Debug, Clone)]
+struct Assignment {
+    key: String,
+    /// The value as written between double quotes, with the quotes removed but
+    /// expansions such as `$NAME` kept, so it can be written back.
+    literal: String,
+    /// The value the shell sees, with expansions resolved.
+    value: String,
+    /// The byte range of the raw value in the file, after the `=`.
+    span: Range<usize>,
+    /// The byte range of the whole assignment, `export` included.
+    statement: Range<usize>,
+    single_quoted: bool,
+}
+
+impl Assignment {
+    /// Quotes a new value of the assignment: between double quotes, so it can contain expansions,
+    /// unless the value was single quoted and needs no escaping.
+    fn quote(&self, literal: &str) -> String {
+        if self.single_quoted && !literal.contains(['\\', '$', '`', '"', '\'']) {
+            format!("'{}'", literal)
+        } else {
+            format!("\"{}\"", literal)
+        }
+    }
+}
+
+/// A file of shell assignments, sourced by a shell script: the GRUB defaults, `ifcfg-*` files
+/// or `os-release`.
+///
+/// The file is parsed like the shell does for assignments (quotes, escapes, continuation lines,
+/// comments and `$VAR` expansions), and edits only replace the value of an assignment,
+/// so the rest of the file is kept as is.
+#[derive(Debug, Clone)]
+pub struct ShellFile {
+    path: String,
+    content: String,
+    assignments: Vec<Assignment>,
+    /// The variables defined before this file was sourced, to expand values again after an edit.
+    env: HashMap<String, String>,
+    modified: bool,
+}
+
+impl ShellFile {
+    /// Parses a file, expanding variables with the ones in `env`, which is then updated
+    /// with the assignments of the file.
+    pub fn parse(path: &str, content: &str, env: &mut HashMap<String, String>) -> Result<Self, ShellFileError> {
+        let initial_env = env.clone();
+        let assignments = Parser { path, content, pos: 0, env }.parse()?;
+        Ok(ShellFile {
+            path: path.to_string(),
+            content: content.to_string(),
+            assignments,
+            env: initial_env,
+            modified: false,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// The keys assigned in this file, in order of their first assignment.
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = vec![];
+        for assignment in &self.assignments {
+            if !keys.contains(&assignment.key.as_str()) {
+                keys.push(&assignment.key);
+            }
+        }
+        keys
+    }
+
+    /// The value of the last assignment of the key in this file, with expansions resolved.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.last(key).map(|assignment| assignment.value.as_str())
+    }
+
+    /// The value of the last assignment of the key as written, with expansions kept.
+    pub fn literal(&self, key: &str) -> Option<&str> {
+        self.last(key).map(|assignment| assignment.literal.as_str())
+    }
+
+    /// Replaces the value of the last assignment of the key, or appends an assignment
+    /// at the end of the file. The value is written between double quotes, so it can
+    /// contain expansions, unless the value was single quoted and needs no escaping.
+    pub fn set(&mut self, key: &str, literal: &str) -> Result<(), ShellFileError> {
+        let mut content = self.content.clone();
+        match self.last(key) {
+            Some(assignment) => content.replace_range(assignment.span.clone(), &assignment.quote(literal)),
+            None => {
+                if !content.is_empty() && !content.ends_with('\n') {
+                    content.push('\n');
+                }
+                content.push_str(&format!("{}=\"{}\"\n", key, literal));
+            }
+        }
+
+        let mut env = self.env.clone();
+        *self = ShellFile::parse(&self.path, &content, &mut env)?;
+        self.modified = true;
+        Ok(())
+    }
+
+    /// Rewrites the value of every assignment of the key with `edit`, which is given the value as
+    /// written. Returns whether a value changed.
+    pub fn edit_literals(&mut self, key: &str, edit: impl Fn(&str) -> String) -> Result<bool, ShellFileError> {
+        let mut content = self.content.clone();
+        let mut changed = false;
+        for assignment in self.assignments.iter().rev().filter(|assignment| assignment.key == key) {
+            let literal = edit(&assignment.literal);
+            if literal != assignment.literal {
+                content.replace_range(assignment.span.clone(), &assignment.quote(&literal));
+                changed = true;
+            }
+        }
+        if !changed {
+            return Ok(false);
+        }
+
+        let mut env = self.env.clone();
+        *self = ShellFile::parse(&self.path, &content, &mut env)?;
+        self.modified = true;
+        Ok(true)
+    }
+
+    /// Removes every assignment of the key, with its line when nothing else is on it.
+    pub fn remove(&mut self, key: &str) -> Result<(), ShellFileError> {
+        let mut content = self.content.clone();
+        let assignments: Vec<&Assignment> = self.assignments.iter().filter(|assignment| assignment.key == key).collect();
+        if assignments.is_empty() {
+            return Ok(());
+        }
+
+        for assignment in assignments.iter().rev() {
+            let line_start = content[..assignment.statement.start].rfind('\n').map(|index| index + 1).unwrap_or(0);
+            let line_end = content[assignment.statement.end..]
+                .find('\n')
+                .map(|index| assignment.statement.end + index + 1)
+                .unwrap_or(content.len());
+            let is_alone = |text: &str| text.trim().trim_matches(';').trim().is_empty();
+            if is_alone(&content[line_start..assignment.statement.start]) && is_alone(&content[assignment.statement.end..line_end]) {
+                content.replace_range(line_start..line_end, "");
+            } else {
+                content.replace_range(statement_with_separator(&content, &assignment.statement), "");
+            }
+        }
+
+        let mut env = self.env.clone();
+        *self = ShellFile::parse(&self.path, &content, &mut env)?;
+        self.modified = true;
+        Ok(())
+    }
+
+    /// The variables defined before this file was sourced.
+    pub fn env(&self) -> &HashMap<String, String> {
+        &self.env
+    }
+
+    /// Expands the values again with the variables in `env`, e.g. after a file sourced
+    /// before this one was edited, and updates `env` with the assignments of the file.
+    pub fn reparse(&mut self, env: &mut HashMap<String, String>) -> Result<(), ShellFileError> {
+        let modified = self.modified;
+        *self = ShellFile::parse(&self.path, &self.content, env)?;
+        self.modified = modified;
+        Ok(())
+    }
+
+    fn last(&self, key: &str) -> Option<&Assignment> {
+        self.assignments.iter().rev().find(|assignment| assignment.key == key)
+    }
+}
+
+/// The range of a statement sharing its line with others, with the `;` separating it from the next
+/// statement, or from the previous one when it is the last, so the line stays valid shell.
+fn statement_with_separator(content: &str, statement: &Range<usize>) -> Range<usize> {
+    let is_blank = |c: char| c == ' ' || c == '\t';
+    let after = &content[statement.end..];
+    let after_blanks = after.trim_start_matches(is_blank);
+    if let Some(rest) = after_blanks.strip_prefix(';') {
+        return statement.start..content.len() - rest.trim_start_matches(is_blank).len();
+    }
+    let before = content[..statement.start].trim_end_matches(is_blank);
+    match before.strip_suffix(';') {
+        Some(rest) => rest.trim_end_matches(is_blank).len()..statement.end,
+        None => statement.clone(),
+    }
+}
+
+/// The length of the shell variable name at the start of the text, 0 if there is none.
+fn name_len(text: &str) -> usize {
+    text.char_indices()
+        .find(|&(index, c)| !(c == '_' || c.is_ascii_alphabetic() || (index > 0 && c.is_ascii_digit())))
+        .map(|(index, _)| index)
+        .unwrap_or(text.len())
+}
+
+struct Parser<'a> {
+    path: &'a str,
+    content: &'a str,
+    pos: usize,
+    env: &'a mut HashMap<String, String>,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(mut self) -> Result<Vec<Assignment>, ShellFileError> {
+        let mut assignments = vec![];
+
+        while self.pos < self.content.len() {
+            self.skip_blanks();
+            match self.peek() {
+                None => break,
+                Some('\n') | Some(';') => self.pos += 1,
+                Some('#') => self.skip_line(),
+                Some(_) => {
+                    if let Some(assignment) = self.statement()? {
+                        self.env.insert(assignment.key.clone(), assignment.value.clone());
+                        assignments.push(assignment);
+                    }
+                }
+            }
+        }
+
+        Ok(assignments)
+    }
+
+    /// Parses a statement, returning the assignment if it is one.
+    /// Other statements, e.g. `if` blocks, are skipped up to the end of the line.
+    fn statement(&mut self) -> Result<Option<Assignment>, ShellFileError> {
+        let statement_start = self.pos;
+        if self.rest().starts_with("export ") {
+            self.pos += "export ".len();
+            self.skip_blanks();
+        }
+
+        let name_len = name_len(self.rest());
+        if name_len == 0 || !self.rest()[name_len..].starts_with('=') {
+            self.word()?;
+            self.skip_statement()?;
+            return Ok(None);
+        }
+
+        let key = self.rest()[..name_len].to_string();
+        self.pos += name_len + 1;
+
+        let start = self.pos;
+        let single_quoted = self.peek() == Some('\'');
+        let (literal, value) = self.word()?;
+        let span = start..self.pos;
+        let single_quoted = single_quoted && self.content[span.clone()].ends_with('\'') && self.content[span.clone()].matches('\'').count() == 2;
+
+        let statement = statement_start..span.end;
+        self.skip_statement()?;
+        Ok(Some(Assignment { key, literal, value, span, statement, single_quoted }))
+    }
+
+    /// Reads a shell word, returning its literal and expanded forms.
+    fn word(&mut self) -> Result<(String, String), ShellFileError> {
+        let mut literal = String::new();
+        let mut value = String::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\n' | ';' => break,
+                '"' => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    loop {
+                        match self.bump() {
+                            None => return Err(self.unterminated(start)),
+                            Some('"') => break,
+                            Some('\\') => match self.bump() {
+                                None => return Err(self.unterminated(start)),
+                                Some('\n') => {}
+                                Some(escaped) if matches!(escaped, '"' | '\\' | '$' | '`') => {
+                                    literal.push('\\');
+                                    literal.push(escaped);
+                                    value.push(escaped);
+                                }
+                                Some(other) => {
+                                    literal.push('\\');
+                                    literal.push(other);
+                                    value.push('\\');
+                                    value.push(other);
+                                }
+                            },
+                            Some('$') => self.expansion(&mut literal, &mut value),
+                            Some(other) => {
+                                literal.push(other);
+                                value.push(other);
+                            }
+                        }
+                    }
+                }
+                '\'' => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    loop {
+                        match self.bump() {
+                            None => return Err(self.unterminated(start)),
+                            Some('\'') => break,
+                            Some(other) => {
+                                if matches!(other, '"' | '\\' | '$' | '`') {
+                                    literal.push('\\');
+                                }
+                                literal.push(other);
+                                value.push(other);
+                            }
+                        }
+                    }
+                }
+                '\\' => {
+                    self.pos += 1;
+                    match self.bump() {
+                        // a continuation line
+                        Some('\n') | None => {}
+                        Some(escaped) => {
+                            if matches!(escaped, '"' | '\\' | '$' | '`') {
+                                literal.push('\\');
+                            }
+                            literal.push(escaped);
+                            value.push(escaped);
+                        }
+                    }
+                }
+                '$' => {
+                    self.pos += 1;
+                    self.expansion(&mut literal, &mut value);
+                }
+                other => {
+                    self.pos += other.len_utf8();
+                    literal.push(other);
+                    value.push(other);
+                }
+            }
+        }
+
+        Ok((literal, value))
+    }
+
+    /// Expands `$NAME` or `${NAME}`, the `$` being already consumed.
+    fn expansion(&mut self, literal: &mut String, value: &mut String) {
+        let braced = self.peek() == Some('{');
+        let rest = if braced { &self.rest()[1..] } else { self.rest() };
+        let name_len = name_len(rest);
+        if name_len == 0 || (braced && !rest[name_len..].starts_with('}')) {
+            literal.push('$');
+            value.push('$');
+            return;
+        }
+
+        let name = rest[..name_len].to_string();
+        let raw_len = if braced { name_len + 2 } else { name_len };
+        literal.push('$');
+        literal.push_str(&self.rest()[..raw_len]);
+        value.push_str(self.env.get(&name).map(String::as_str).unwrap_or(""));
+        self.pos += raw_len;
+    }
+
+    /// Skips the rest of a statement: further words, and a trailing comment.
+    fn skip_statement(&mut self) -> Result<(), ShellFileError> {
+        loop {
+            self.skip_blanks();
+            match self.peek() {
+                None | Some('\n') | Some(';') => return Ok(()),
+                Some('#') => {
+                    self.skip_line();
+                    return Ok(());
+                }
+                Some(_) => {
+                    self.word()?;
+                }
+            }
+        }
+    }
+
+    fn skip_blanks(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == ' ' || c == '\t' {
+                self.pos += 1;
+            } else if self.rest().starts_with("\\\n") {
+                self.pos += 2;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn skip_line(&mut self) {
+        self.pos = self.rest().find('\n').map(|index| self.pos + index).unwrap_or(self.content.len());
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.content[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn unterminated(&self, start: usize) -> ShellFileError {
+        ShellFileError::UnterminatedQuote {
+            path: self.path.to_string(),
+            line: self.content[..start].matches('\n').count() + 1,
+        }
+    }
+}
+
+#[test]
+fn test_parse_shell_assignments() {
+    let content = "# GRUB defaults\n\
+                   GRUB_DEFAULT=0\n\
+                   GRUB_TIMEOUT_STYLE='hidden' # comment\n\
+                   export GRUB_CMDLINE_LINUX_DEFAULT=\"quiet \\\n  splash\"\n\
+                   GRUB_CMDLINE_LINUX=\"console=tty1 root=UUID=1234\"\n\
+                   if [ -x /usr/bin/foo ]; then GRUB_DISABLE_OS_PROBER=true; fi\n";
+
+    let file = ShellFile::parse("/etc/default/grub", content, &mut HashMap::new()).unwrap();
+
+    assert_eq!(file.get("GRUB_DEFAULT"), Some("0"));
+    assert_eq!(file.get("GRUB_TIMEOUT_STYLE"), Some("hidden"));
+    assert_eq!(file.get("GRUB_CMDLINE_LINUX_DEFAULT"), Some("quiet   splash"));
+    assert_eq!(file.get("GRUB_CMDLINE_LINUX"), Some("console=tty1 root=UUID=1234"));
+    assert_eq!(file.get("GRUB_DISABLE_OS_PROBER"), None);
+}
+
+#[test]
+fn test_round_trip_without_edits() {
+    let content = "GRUB_CMDLINE_LINUX=\"a=\\\"b\\\" \\$HOME\" \nGRUB_X=a'b c'\"$GRUB_CMDLINE_LINUX\"\n";
+    let mut file = ShellFile::parse("/etc/default/grub", content, &mut HashMap::new()).unwrap();
+    let literal = file.literal("GRUB_CMDLINE_LINUX").unwrap().to_string();
+
+    file.set("GRUB_CMDLINE_LINUX", &literal).unwrap();
+
+    assert_eq!(file.content(), content);
+    assert_eq!(file.get("GRUB_CMDLINE_LINUX"), Some("a=\"b\" $HOME"));
+    assert_eq!(file.get("GRUB_X"), Some("ab ca=\"b\" $HOME"));
+}
+
+#[test]
+fn test_remove_keeps_the_other_statements() {
+    let content = "TYPE=Ethernet\nHWADDR=52:54:00:ab:cd:ef\nexport IPADDR=10.0.0.5; PREFIX=24\n";
+    let mut file = ShellFile::parse("/etc/sysconfig/network-scripts/ifcfg-eth0", content, &mut HashMap::new()).unwrap();
+
+    file.remove("HWADDR").unwrap();
+    file.remove("IPADDR").unwrap();
+
+    assert_eq!(file.content(), "TYPE=Ethernet\nPREFIX=24\n");
+    assert_eq!(file.keys(), vec!["TYPE", "PREFIX"]);
+    let mut file = ShellFile::parse("/etc/sysconfig/network-scripts/ifcfg-eth0", "IPADDR=10.0.0.5 ; PREFIX=24\n", &mut HashMap::new()).unwrap();
+    file.remove("PREFIX").unwrap();
+    assert_eq!(file.content(), "IPADDR=10.0.0.5\n");
+}